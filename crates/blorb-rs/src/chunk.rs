@@ -1,10 +1,11 @@
 use std::fmt::{Debug, Formatter};
 
-use crate::{error::BlorbError, types::*};
+use crate::{error::BlorbError, metadata::BlorbMetadata, types::*};
 
 /// A raw IFRS chunk
 pub struct RawBlorbChunk<'a> {
     usage: Option<ResourceType>,
+    offset: usize,
     /// The type of data stored in the bytes field
     pub blorb_type: BlorbType,
     /// Raw data from the blorb file
@@ -61,6 +62,16 @@ pub enum BlorbChunk {
 
     /// A list of picture resources which have adaptive palette colors
     AdaptivePalette(Vec<usize>),
+
+    /// The colors that adaptive palette pictures should be drawn with, as (red, green, blue)
+    Palette(Vec<(u8, u8, u8)>),
+
+    /// The game prefers direct color with the given number of bits per pixel (16 or 32)
+    /// instead of a color palette
+    DirectColor(u8),
+
+    /// Bibliographic metadata for the game (Blorb Spec section 11)
+    Metadata(BlorbMetadata),
 }
 
 /// The size of a window for the resolution chunk
@@ -159,14 +170,39 @@ pub struct TextDescription {
 }
 
 impl<'a> RawBlorbChunk<'a> {
-    pub(crate) fn new(blorb_type: BlorbType, bytes: &'a [u8]) -> RawBlorbChunk {
+    pub(crate) fn new(blorb_type: BlorbType, offset: usize, bytes: &'a [u8]) -> RawBlorbChunk {
         Self {
             usage: None,
+            offset,
             blorb_type,
             bytes,
         }
     }
 
+    /// The four-character code of the chunk, exactly as it appears in the blorb file
+    pub fn type_code(&self) -> [u8; 4] {
+        self.blorb_type.type_code()
+    }
+
+    /// The offset of the chunk's header in the blorb file
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn conversion_failed(&self) -> BlorbError {
+        BlorbError::ConversionFailed {
+            blorb_type: self.blorb_type,
+            offset: self.offset,
+        }
+    }
+
+    pub(crate) fn invalid_utf8(&self) -> BlorbError {
+        BlorbError::InvalidUtf8String {
+            blorb_type: self.blorb_type,
+            offset: self.offset,
+        }
+    }
+
     pub(crate) fn with_usage(mut self, usage: ResourceType) -> Self {
         self.usage = Some(usage);
         self
@@ -206,13 +242,13 @@ impl BlorbChunk {
     pub fn elbow_room(&self, wx: usize, wy: usize) -> Result<f64, BlorbError> {
         if let Self::Resolution { standard, .. } = self {
             if standard.width == 0 || standard.height == 0 {
-                return Err(BlorbError::ConversionFailed);
+                return Err(BlorbError::InvalidResolution);
             }
             let erf_width = wx as f64 / standard.width as f64;
             let erf_height = wy as f64 / standard.height as f64;
             Ok(erf_width.min(erf_height))
         } else {
-            Err(BlorbError::ConversionFailed)
+            Err(BlorbError::InvalidResolution)
         }
     }
 }
@@ -222,56 +258,76 @@ impl<'a> TryFrom<&RawBlorbChunk<'a>> for BlorbChunk {
     type Error = BlorbError;
 
     fn try_from(bc: &RawBlorbChunk<'a>) -> Result<Self, BlorbError> {
+        let failed = || bc.conversion_failed();
+        let usize_at = |start| bytes_to_usize(field(bc.bytes, start, 4)?);
+        let string_at = |start, len| {
+            let bytes = field(bc.bytes, start, len).ok_or_else(failed)?;
+            bytes_to_string(bytes).ok_or_else(|| bc.invalid_utf8())
+        };
+
         match bc.blorb_type {
-            BlorbType::Fspc => Ok(Self::Frontispiece(bytes_to_usize(bc.bytes)?)),
-            BlorbType::Auth => Ok(Self::Author(bytes_to_string(bc.bytes)?)),
-            BlorbType::Copr => Ok(Self::Copyright(bytes_to_string(bc.bytes)?)),
-            BlorbType::Anno => Ok(Self::Annotation(bytes_to_string(bc.bytes)?)),
-            BlorbType::Reln => Ok(Self::ReleaseNumber(bytes_to_u16(&bc.bytes[0..2])?)),
+            BlorbType::Fspc => Ok(Self::Frontispiece(
+                bytes_to_usize(bc.bytes).ok_or_else(failed)?,
+            )),
+            BlorbType::Auth => Ok(Self::Author(string_at(0, bc.bytes.len())?)),
+            BlorbType::Copr => Ok(Self::Copyright(string_at(0, bc.bytes.len())?)),
+            BlorbType::Anno => Ok(Self::Annotation(string_at(0, bc.bytes.len())?)),
+            BlorbType::Ifmd => Ok(Self::Metadata(BlorbMetadata::from_chunk(bc)?)),
+            BlorbType::Reln => Ok(Self::ReleaseNumber(
+                bytes_to_u16(bc.bytes).ok_or_else(failed)?,
+            )),
             BlorbType::Apal => {
-                if bc.bytes.is_empty() {
-                    return Ok(Self::AdaptivePalette(Vec::new()));
-                }
-
-                let num = bytes_to_usize(&bc.bytes[0..4])?;
+                // the chunk data is nothing but a list of 4-byte resource numbers
+                let num = bc.bytes.len();
                 if num % 4 != 0 {
-                    return Err(BlorbError::ConversionFailed);
+                    return Err(failed());
                 }
                 let mut entries = Vec::new();
-                for i in 0..num % 4 {
-                    let start = 4 + i * 4;
-                    entries.push(bytes_to_usize(&bc.bytes[start..start + 4])?);
+                for i in 0..num / 4 {
+                    entries.push(usize_at(i * 4).ok_or_else(failed)?);
                 }
                 Ok(Self::AdaptivePalette(entries))
             }
+            BlorbType::Plte => match bc.bytes {
+                // a single byte holds a color depth rather than a color
+                [depth @ (16 | 32)] => Ok(Self::DirectColor(*depth)),
+                bytes if bytes.len() % 3 == 0 => Ok(Self::Palette(
+                    bytes
+                        .chunks_exact(3)
+                        .map(|rgb| (rgb[0], rgb[1], rgb[2]))
+                        .collect(),
+                )),
+                _ => Err(failed()),
+            },
             BlorbType::Ifhd => {
                 if bc.bytes.len() != 13 {
-                    return Err(BlorbError::ConversionFailed);
+                    return Err(failed());
                 }
                 let mut serial_number = [0; 6];
                 let mut pc = [0; 3];
                 serial_number.clone_from_slice(&bc.bytes[2..8]);
                 pc.clone_from_slice(&bc.bytes[10..13]);
                 Ok(Self::GameIdentifier {
-                    release_number: bytes_to_u16(&bc.bytes[0..2])?,
+                    release_number: bytes_to_u16(&bc.bytes[0..2]).ok_or_else(failed)?,
                     serial_number,
-                    checksum: bytes_to_u16(&bc.bytes[8..10])?,
+                    checksum: bytes_to_u16(&bc.bytes[8..10]).ok_or_else(failed)?,
                     pc,
                 })
             }
             BlorbType::Rect => {
-                let width = bytes_to_usize(&bc.bytes[0..4])?;
-                let height = bytes_to_usize(&bc.bytes[4..8])?;
+                let width = usize_at(0).ok_or_else(failed)?;
+                let height = usize_at(4).ok_or_else(failed)?;
                 Ok(Self::Placeholder(width, height))
             }
             BlorbType::Rdes => {
                 let mut entries = Vec::new();
                 let mut offset = 4;
-                for _ in 0..bytes_to_usize(&bc.bytes[0..4])? {
-                    let usage: ResourceType = bc.bytes[offset..offset + 4].try_into()?;
-                    let number = bytes_to_usize(&bc.bytes[offset + 4..offset + 8])?;
-                    let len = bytes_to_usize(&bc.bytes[offset + 8..offset + 12])?;
-                    let text = bytes_to_string(&bc.bytes[offset + 12..offset + 12 + len])?;
+                for _ in 0..usize_at(0).ok_or_else(failed)? {
+                    let usage: ResourceType =
+                        field(bc.bytes, offset, 4).ok_or_else(failed)?.try_into()?;
+                    let number = usize_at(offset + 4).ok_or_else(failed)?;
+                    let len = usize_at(offset + 8).ok_or_else(failed)?;
+                    let text = string_at(offset + 12, len)?;
                     entries.push(TextDescription {
                         usage,
                         number,
@@ -282,54 +338,55 @@ impl<'a> TryFrom<&RawBlorbChunk<'a>> for BlorbChunk {
                 Ok(Self::ResourceDescription(entries))
             }
             BlorbType::Reso => {
-                let entry_count = bc.bytes.len() - 24;
+                let entry_count = bc.bytes.len().checked_sub(24).ok_or_else(failed)?;
                 if entry_count % 28 != 0 {
-                    return Err(BlorbError::ConversionFailed);
+                    return Err(failed());
                 }
 
                 let entry_count = entry_count / 28;
 
-                let px = bytes_to_usize(&bc.bytes[0..4])?;
-                let py = bytes_to_usize(&bc.bytes[4..8])?;
+                let px = usize_at(0).ok_or_else(failed)?;
+                let py = usize_at(4).ok_or_else(failed)?;
                 let standard = WindowSize {
                     width: px,
                     height: py,
                 };
 
-                let minx = bytes_to_usize(&bc.bytes[8..12])?;
-                let miny = bytes_to_usize(&bc.bytes[12..16])?;
+                let minx = usize_at(8).ok_or_else(failed)?;
+                let miny = usize_at(12).ok_or_else(failed)?;
                 let minimum = WindowSize {
                     width: minx,
                     height: miny,
                 };
 
-                let maxx = bytes_to_usize(&bc.bytes[16..20])?;
-                let maxy = bytes_to_usize(&bc.bytes[20..24])?;
+                let maxx = usize_at(16).ok_or_else(failed)?;
+                let maxy = usize_at(20).ok_or_else(failed)?;
                 let maximum = WindowSize {
                     width: maxx,
                     height: maxy,
                 };
 
+                // entries follow the 24-byte window size header
                 let mut entries = Vec::new();
-                let mut offset = 4;
+                let mut offset = 24;
                 for _ in 0..entry_count {
-                    let number = bytes_to_usize(&bc.bytes[offset..offset + 4])?;
-                    let ratnum = bytes_to_usize(&bc.bytes[offset + 4..offset + 8])?;
-                    let ratden = bytes_to_usize(&bc.bytes[offset + 8..offset + 12])?;
+                    let number = usize_at(offset).ok_or_else(failed)?;
+                    let ratnum = usize_at(offset + 4).ok_or_else(failed)?;
+                    let ratden = usize_at(offset + 8).ok_or_else(failed)?;
                     let standard = ResolutionRatio {
                         numerator: ratnum,
                         denominator: ratden,
                     };
 
-                    let minnum = bytes_to_usize(&bc.bytes[offset + 12..offset + 16])?;
-                    let minden = bytes_to_usize(&bc.bytes[offset + 16..offset + 20])?;
+                    let minnum = usize_at(offset + 12).ok_or_else(failed)?;
+                    let minden = usize_at(offset + 16).ok_or_else(failed)?;
                     let minimum = ResolutionRatio {
                         numerator: minnum,
                         denominator: minden,
                     };
 
-                    let maxnum = bytes_to_usize(&bc.bytes[offset + 20..offset + 24])?;
-                    let maxden = bytes_to_usize(&bc.bytes[offset + 24..offset + 28])?;
+                    let maxnum = usize_at(offset + 20).ok_or_else(failed)?;
+                    let maxden = usize_at(offset + 24).ok_or_else(failed)?;
                     let maximum = ResolutionRatio {
                         numerator: maxnum,
                         denominator: maxden,
@@ -349,34 +406,39 @@ impl<'a> TryFrom<&RawBlorbChunk<'a>> for BlorbChunk {
                     entries,
                 })
             }
-            _ => Err(BlorbError::ConversionFailed),
+            _ => Err(failed()),
         }
     }
 }
 
-fn bytes_to_string(bytes: &[u8]) -> Result<String, BlorbError> {
-    Ok(std::str::from_utf8(bytes)
-        .map_err(|_| BlorbError::InvalidUtf8String)?
-        .to_string())
+// returns len bytes starting at start, or None if the chunk is too short
+fn field(bytes: &[u8], start: usize, len: usize) -> Option<&[u8]> {
+    start.checked_add(len).and_then(|end| bytes.get(start..end))
+}
+
+fn bytes_to_string(bytes: &[u8]) -> Option<String> {
+    std::str::from_utf8(bytes).ok().map(str::to_string)
 }
 
-fn bytes_to_u16(bytes: &[u8]) -> Result<u16, BlorbError> {
+fn bytes_to_u16(bytes: &[u8]) -> Option<u16> {
     if bytes.len() != 2 {
-        Err(BlorbError::ConversionFailed)
+        None
     } else {
-        Ok((bytes[0] as u16) << 8 | (bytes[1] as u16))
+        Some((bytes[0] as u16) << 8 | (bytes[1] as u16))
     }
 }
 
-fn bytes_to_usize(bytes: &[u8]) -> Result<usize, BlorbError> {
+fn bytes_to_usize(bytes: &[u8]) -> Option<usize> {
     if bytes.len() != 4 {
-        Err(BlorbError::ConversionFailed)
+        None
     } else {
         // TODO: refactor with BlorbReader's version
-        Ok((bytes[0] as usize) << 24
-            | (bytes[1] as usize) << 16
-            | (bytes[2] as usize) << 8
-            | (bytes[3] as usize))
+        Some(
+            (bytes[0] as usize) << 24
+                | (bytes[1] as usize) << 16
+                | (bytes[2] as usize) << 8
+                | (bytes[3] as usize),
+        )
     }
 }
 
@@ -395,6 +457,7 @@ mod test {
 
         let rbc = RawBlorbChunk {
             usage: None,
+            offset: 0,
             blorb_type: BlorbType::Rdes,
             bytes: &bytes,
         };
@@ -410,6 +473,7 @@ mod test {
         let bytes = [0u8, 0, 1, 0, 0, 0, 2, 0];
         let rbc = RawBlorbChunk {
             usage: None,
+            offset: 0,
             blorb_type: BlorbType::Rect,
             bytes: &bytes,
         };
@@ -417,6 +481,180 @@ mod test {
         assert_eq!(BlorbChunk::Placeholder(256, 512), rdes);
     }
 
+    #[test]
+    fn can_read_all_adaptive_palette_entries() {
+        let bytes = [0u8, 0, 0, 3, 0, 0, 0, 7, 0, 0, 1, 2];
+        let rbc = RawBlorbChunk {
+            usage: None,
+            offset: 0,
+            blorb_type: BlorbType::Apal,
+            bytes: &bytes,
+        };
+        let apal: BlorbChunk = (&rbc).try_into().expect("could not convert");
+        assert_eq!(BlorbChunk::AdaptivePalette(vec![3, 7, 258]), apal);
+    }
+
+    #[test]
+    fn adaptive_palette_must_be_a_multiple_of_four_bytes() {
+        let bytes = [0u8, 0, 0, 3, 0, 0];
+        let rbc = RawBlorbChunk {
+            usage: None,
+            offset: 0,
+            blorb_type: BlorbType::Apal,
+            bytes: &bytes,
+        };
+        let apal: Result<BlorbChunk, BlorbError> = (&rbc).try_into();
+        assert_eq!(
+            Err(BlorbError::ConversionFailed {
+                blorb_type: BlorbType::Apal,
+                offset: 0
+            }),
+            apal
+        );
+    }
+
+    #[test]
+    fn type_code_keeps_trailing_spaces() {
+        let rbc = RawBlorbChunk::new(BlorbType::Png, 0, &[]);
+        assert_eq!(*b"PNG ", rbc.type_code());
+        assert_eq!(*b"(c) ", BlorbType::Copr.type_code());
+        assert_eq!(*b"Snd ", ResourceType::Sound.type_code());
+    }
+
+    #[test]
+    fn conversion_errors_name_the_chunk() {
+        let rbc = RawBlorbChunk::new(BlorbType::Fspc, 48, &[0, 1]);
+        let err = BlorbChunk::try_from(&rbc).expect_err("converted a short chunk");
+        assert_eq!(
+            BlorbError::ConversionFailed {
+                blorb_type: BlorbType::Fspc,
+                offset: 48
+            },
+            err
+        );
+        assert!(err.is_conversion_failure());
+
+        let rbc = RawBlorbChunk::new(BlorbType::Auth, 96, &[0xff, 0xfe]);
+        let err = BlorbChunk::try_from(&rbc).expect_err("converted a non-utf8 chunk");
+        assert_eq!(
+            BlorbError::InvalidUtf8String {
+                blorb_type: BlorbType::Auth,
+                offset: 96
+            },
+            err
+        );
+        assert!(!err.is_conversion_failure());
+    }
+
+    #[test]
+    fn can_decode_a_color_palette() {
+        let bytes = [255u8, 0, 0, 0, 128, 0, 16, 32, 64];
+        let rbc = RawBlorbChunk::new(BlorbType::Plte, 0, &bytes);
+        assert_eq!(
+            Ok(BlorbChunk::Palette(vec![
+                (255, 0, 0),
+                (0, 128, 0),
+                (16, 32, 64)
+            ])),
+            BlorbChunk::try_from(&rbc)
+        );
+
+        let rbc = RawBlorbChunk::new(BlorbType::Plte, 0, &[32]);
+        assert_eq!(Ok(BlorbChunk::DirectColor(32)), BlorbChunk::try_from(&rbc));
+
+        let rbc = RawBlorbChunk::new(BlorbType::Plte, 0, &[1, 2, 3, 4]);
+        assert!(BlorbChunk::try_from(&rbc)
+            .unwrap_err()
+            .is_conversion_failure());
+    }
+
+    #[test]
+    fn can_read_resolution_entries_after_the_header() {
+        let bytes = [
+            0u8, 0, 0, 80, 0, 0, 0, 25, // standard window size
+            0, 0, 0, 40, 0, 0, 0, 10, // minimum window size
+            0, 0, 0, 160, 0, 0, 0, 50, // maximum window size
+            0, 0, 0, 7, // image resource number
+            0, 0, 0, 1, 0, 0, 0, 1, // standard ratio
+            0, 0, 0, 1, 0, 0, 0, 2, // minimum ratio
+            0, 0, 0, 3, 0, 0, 0, 1, // maximum ratio
+        ];
+        let rbc = RawBlorbChunk {
+            usage: None,
+            offset: 0,
+            blorb_type: BlorbType::Reso,
+            bytes: &bytes,
+        };
+        let reso: BlorbChunk = (&rbc).try_into().expect("could not convert");
+        let BlorbChunk::Resolution {
+            standard, entries, ..
+        } = reso
+        else {
+            panic!("invalid conversion");
+        };
+        assert_eq!(
+            WindowSize {
+                width: 80,
+                height: 25
+            },
+            standard
+        );
+        assert_eq!(
+            vec![ResolutionEntry {
+                number: 7,
+                standard: ResolutionRatio {
+                    numerator: 1,
+                    denominator: 1
+                },
+                minimum: ResolutionRatio {
+                    numerator: 1,
+                    denominator: 2
+                },
+                maximum: ResolutionRatio {
+                    numerator: 3,
+                    denominator: 1
+                },
+            }],
+            entries
+        );
+    }
+
+    #[test]
+    fn empty_chunks_do_not_panic() {
+        for blorb_type in [
+            BlorbType::Fspc,
+            BlorbType::Auth,
+            BlorbType::Copr,
+            BlorbType::Anno,
+            BlorbType::Ifmd,
+            BlorbType::Reln,
+            BlorbType::Apal,
+            BlorbType::Ifhd,
+            BlorbType::Rect,
+            BlorbType::Rdes,
+            BlorbType::Reso,
+        ] {
+            let rbc = RawBlorbChunk {
+                usage: None,
+                offset: 0,
+                blorb_type,
+                bytes: &[],
+            };
+            let _ = BlorbChunk::try_from(&rbc);
+        }
+
+        let rbc = RawBlorbChunk {
+            usage: None,
+            offset: 0,
+            blorb_type: BlorbType::Auth,
+            bytes: &[],
+        };
+        assert_eq!(
+            Ok(BlorbChunk::Author(String::new())),
+            BlorbChunk::try_from(&rbc)
+        );
+    }
+
     fn implements_debug<T: Debug>() {}
 
     #[test]
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::types::{BlorbType, ResourceType};
+
 /// Error module
 #[derive(Error, Debug, PartialEq)]
 pub enum BlorbError {
@@ -15,6 +17,10 @@ pub enum BlorbError {
     #[error("No such resource {0}")]
     NonExistentResource(usize),
 
+    /// The blorb file has no resources with the requested usage
+    #[error("No {0:?} resources")]
+    NoResourcesOfType(ResourceType),
+
     /// The requested chunk type was not found in the blorb file
     #[error("Chunk not found")]
     ChunkNotFound,
@@ -23,11 +29,65 @@ pub enum BlorbError {
     #[error("End of file")]
     EndOfFile,
 
+    /// A chunk or field extends past the end of the file
+    #[error("Truncated chunk")]
+    TruncatedChunk,
+
+    /// The underlying reader failed
+    #[error("Read error: {0}")]
+    ReadError(String),
+
+    /// The picture resource is not a PNG or JPEG image
+    #[error("Unsupported image format")]
+    UnsupportedImageFormat,
+
+    /// The sound resource is not an AIFF, MOD, Ogg Vorbis or SONG file
+    #[error("Unsupported sound format")]
+    UnsupportedSoundFormat,
+
     /// Could not convert generic blorb type into a known chunk type
-    #[error("Cannot convert")]
-    ConversionFailed,
+    #[error("Cannot convert {blorb_type:?} chunk at offset {offset}")]
+    ConversionFailed {
+        /// The type of the chunk that could not be converted
+        blorb_type: BlorbType,
+        /// The offset of the chunk in the blorb file
+        offset: usize,
+    },
 
     /// Could not convert slice of bytes into a valid utf8 string
-    #[error("Not a utf8 string")]
-    InvalidUtf8String,
+    #[error("{blorb_type:?} chunk at offset {offset} is not a utf8 string")]
+    InvalidUtf8String {
+        /// The type of the chunk holding the string
+        blorb_type: BlorbType,
+        /// The offset of the chunk in the blorb file
+        offset: usize,
+    },
+
+    /// The chunk is not a resolution chunk with a usable standard window size
+    #[error("Invalid resolution")]
+    InvalidResolution,
+
+    /// The IFmd metadata chunk does not contain well-formed XML
+    #[error("Invalid metadata: {0}")]
+    InvalidMetadata(String),
+
+    /// The file's length does not match the size in its FORM header
+    #[error("File should be {declared} bytes, but is {actual} bytes")]
+    SizeMismatch {
+        /// The length of the file according to its FORM header
+        declared: usize,
+        /// The actual length of the file
+        actual: usize,
+    },
+
+    /// The padding byte after an odd-length chunk was not zero
+    #[error("Non-zero padding byte at offset {0}")]
+    InvalidPadding(usize),
+}
+
+impl BlorbError {
+    /// Is this a failure to convert a chunk, whichever chunk it was
+    pub fn is_conversion_failure(&self) -> bool {
+        matches!(self, Self::ConversionFailed { .. })
+    }
 }
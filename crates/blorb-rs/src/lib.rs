@@ -6,7 +6,7 @@
 //! let blorb_file = BlorbReader::new(bytes);
 //!
 //! let gamedata: &[u8] = blorb_file.get_resource_by_id(0);
-//! let imagedata: &[u8] = blorb_file.get_resource_by_type(BlorbType::PNG);
+//! let imagedata: &[u8] = blorb_file.get_first_rsrc_by_type(BlorbType::Png);
 //!
 
 /// reader
@@ -19,7 +19,17 @@ pub mod chunk;
 /// errors
 pub mod error;
 
+/// metadata
+pub mod metadata;
+
+/// sounds
+pub mod sound;
+
 /// types
 pub mod types;
 
+/// helpers for building blorb files in tests
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
+
 mod stream;
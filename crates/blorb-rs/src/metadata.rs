@@ -0,0 +1,308 @@
+use crate::chunk::RawBlorbChunk;
+use crate::error::BlorbError;
+
+/// Bibliographic information about a game, decoded from the Treaty of Babel "iFiction"
+/// XML stored in an IFmd chunk (Blorb Spec section 11)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlorbMetadata {
+    /// The game's IFID
+    pub ifid: Option<String>,
+    /// The title of the game
+    pub title: Option<String>,
+    /// The author (or authors) of the game
+    pub author: Option<String>,
+    /// The game's headline, e.g. "An Interactive Fiction"
+    pub headline: Option<String>,
+    /// A description of the game. Paragraph breaks are converted to newlines
+    pub description: Option<String>,
+}
+
+/// A summary of a game, gathered from the blorb file's metadata and release chunks
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct GameInfo {
+    /// The game's IFID
+    pub ifid: Option<String>,
+    /// The title of the game
+    pub title: Option<String>,
+    /// The author (or authors) of the game
+    pub author: Option<String>,
+    /// The game's headline
+    pub headline: Option<String>,
+    /// A description of the game
+    pub description: Option<String>,
+    /// The release number from the RelN chunk
+    pub release: Option<u16>,
+}
+
+impl BlorbMetadata {
+    /// Decode the iFiction XML from an IFmd chunk
+    pub(crate) fn from_chunk(chunk: &RawBlorbChunk) -> Result<Self, BlorbError> {
+        let xml = std::str::from_utf8(chunk.bytes).map_err(|_| chunk.invalid_utf8())?;
+        Self::from_xml(xml)
+    }
+
+    fn from_xml(xml: &str) -> Result<Self, BlorbError> {
+        let mut metadata = Self::default();
+        // each open element with the text gathered inside it so far
+        let mut open_tags: Vec<(&str, String)> = Vec::new();
+        let mut rest = xml;
+
+        while let Some(start) = rest.find('<') {
+            let text = unescape(&rest[..start])?;
+            if let Some((_, inner)) = open_tags.last_mut() {
+                inner.push_str(&text);
+            }
+            rest = &rest[start..];
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment
+                    .find("-->")
+                    .ok_or_else(|| invalid("unterminated comment"))?;
+                rest = &comment[end + 3..];
+                continue;
+            }
+
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata
+                    .find("]]>")
+                    .ok_or_else(|| invalid("unterminated CDATA section"))?;
+                if let Some((_, inner)) = open_tags.last_mut() {
+                    inner.push_str(&cdata[..end]);
+                }
+                rest = &cdata[end + 3..];
+                continue;
+            }
+
+            let end = tag_end(rest).ok_or_else(|| invalid("unterminated tag"))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if tag.starts_with('?') || tag.starts_with('!') {
+                // xml declaration or doctype
+                continue;
+            }
+
+            if let Some(name) = tag.strip_prefix('/') {
+                let name = name.trim();
+                let Some((open, inner)) = open_tags.pop().filter(|(open, _)| *open == name) else {
+                    return Err(invalid(&format!("unexpected </{name}>")));
+                };
+                let inner = inner.trim();
+                metadata.set_field(open, inner);
+                // text inside a nested element is also part of its parent's text
+                if let Some((_, outer)) = open_tags.last_mut() {
+                    outer.push_str(inner);
+                }
+            } else if let Some(empty) = tag.strip_suffix('/') {
+                if tag_name(empty)? == "br" {
+                    if let Some((_, inner)) = open_tags.last_mut() {
+                        inner.push('\n');
+                    }
+                }
+            } else {
+                open_tags.push((tag_name(tag)?, String::new()));
+            }
+        }
+
+        if let Some((name, _)) = open_tags.pop() {
+            return Err(invalid(&format!("<{name}> is not closed")));
+        }
+
+        Ok(metadata)
+    }
+
+    // only the first occurrence of each field is kept
+    fn set_field(&mut self, name: &str, value: &str) {
+        let field = match name {
+            "ifid" => &mut self.ifid,
+            "title" => &mut self.title,
+            "author" => &mut self.author,
+            "headline" => &mut self.headline,
+            "description" => &mut self.description,
+            _ => return,
+        };
+
+        if field.is_none() {
+            *field = Some(value.to_string());
+        }
+    }
+}
+
+fn invalid(reason: &str) -> BlorbError {
+    BlorbError::InvalidMetadata(reason.to_string())
+}
+
+// find the '>' that ends the tag at the start of `s`, skipping any inside quoted
+// attribute values
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (pos, ch) in s.char_indices() {
+        match (quote, ch) {
+            (None, '>') => return Some(pos),
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(q), _) if q == ch => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn tag_name(tag: &str) -> Result<&str, BlorbError> {
+    tag.split_whitespace()
+        .next()
+        .ok_or_else(|| invalid("empty tag"))
+}
+
+fn unescape(s: &str) -> Result<String, BlorbError> {
+    let mut result = String::new();
+    let mut rest = s;
+
+    while let Some(amp) = rest.find('&') {
+        result.push_str(&rest[..amp]);
+        rest = &rest[amp + 1..];
+
+        let semi = rest
+            .find(';')
+            .ok_or_else(|| invalid("unterminated entity"))?;
+        let entity = &rest[..semi];
+        rest = &rest[semi + 1..];
+
+        let ch = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => if let Some(hex) = entity.strip_prefix("#x") {
+                u32::from_str_radix(hex, 16).ok()
+            } else if let Some(dec) = entity.strip_prefix('#') {
+                dec.parse().ok()
+            } else {
+                None
+            }
+            .and_then(char::from_u32),
+        };
+        result.push(ch.ok_or_else(|| invalid(&format!("unknown entity &{entity};")))?);
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const IFICTION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ifindex version="1.0" xmlns="http://babel.ifarchive.org/protocol/iFiction/">
+  <!-- a sample record -->
+  <story>
+    <identification>
+      <ifid>ZCODE-1-851126-A4D3</ifid>
+      <format>zcode</format>
+    </identification>
+    <bibliographic>
+      <title>Tom &amp; Jerry</title>
+      <author>A. Writer</author>
+      <headline>An Interactive Fiction</headline>
+      <description>First paragraph.<br/>Second paragraph.</description>
+    </bibliographic>
+  </story>
+</ifindex>"#;
+
+    #[test]
+    fn can_decode_ifiction_xml() {
+        let metadata = BlorbMetadata::from_xml(IFICTION).expect("could not decode");
+        assert_eq!(metadata.ifid.as_deref(), Some("ZCODE-1-851126-A4D3"));
+        assert_eq!(metadata.title.as_deref(), Some("Tom & Jerry"));
+        assert_eq!(metadata.author.as_deref(), Some("A. Writer"));
+        assert_eq!(metadata.headline.as_deref(), Some("An Interactive Fiction"));
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("First paragraph.\nSecond paragraph.")
+        );
+    }
+
+    #[test]
+    fn predefined_entities_are_decoded() {
+        for (entity, expected) in [
+            ("&amp;", "&"),
+            ("&lt;", "<"),
+            ("&gt;", ">"),
+            ("&quot;", "\""),
+            ("&apos;", "'"),
+        ] {
+            let metadata = BlorbMetadata::from_xml(&format!("<title>a{entity}b</title>"))
+                .expect("could not decode");
+            assert_eq!(metadata.title, Some(format!("a{expected}b")));
+        }
+    }
+
+    #[test]
+    fn character_references_are_decoded() {
+        let metadata =
+            BlorbMetadata::from_xml("<title>caf&#233; &#x2603;</title>").expect("could not decode");
+        assert_eq!(metadata.title.as_deref(), Some("caf\u{e9} \u{2603}"));
+    }
+
+    #[test]
+    fn unknown_entities_are_invalid() {
+        assert!(matches!(
+            BlorbMetadata::from_xml("<title>a&nbsp;b</title>"),
+            Err(BlorbError::InvalidMetadata(_))
+        ));
+    }
+
+    #[test]
+    fn attributes_are_skipped() {
+        let metadata = BlorbMetadata::from_xml(
+            r#"<story id="1"><title lang='en' note="a > b">T</title><description>A<br clear="all"/>B</description></story>"#,
+        )
+        .expect("could not decode");
+        assert_eq!(metadata.title.as_deref(), Some("T"));
+        assert_eq!(metadata.description.as_deref(), Some("A\nB"));
+    }
+
+    #[test]
+    fn nested_element_text_is_kept() {
+        let metadata =
+            BlorbMetadata::from_xml("<description>An <em>important</em> game</description>")
+                .expect("could not decode");
+        assert_eq!(metadata.description.as_deref(), Some("An important game"));
+    }
+
+    #[test]
+    fn cdata_is_kept_as_written() {
+        let metadata = BlorbMetadata::from_xml("<title><![CDATA[Tom & <Jerry>]]></title>")
+            .expect("could not decode");
+        assert_eq!(metadata.title.as_deref(), Some("Tom & <Jerry>"));
+    }
+
+    #[test]
+    fn missing_fields_are_none() {
+        let metadata = BlorbMetadata::from_xml(
+            "<ifindex><story><bibliographic><title>T</title></bibliographic></story></ifindex>",
+        )
+        .expect("could not decode");
+        assert_eq!(metadata.title.as_deref(), Some("T"));
+        assert!(metadata.author.is_none());
+        assert!(metadata.ifid.is_none());
+    }
+
+    #[test]
+    fn mismatched_tags_are_invalid() {
+        assert!(matches!(
+            BlorbMetadata::from_xml("<ifindex><story><title>T</author></story></ifindex>"),
+            Err(BlorbError::InvalidMetadata(_))
+        ));
+    }
+
+    #[test]
+    fn unclosed_tags_are_invalid() {
+        assert!(matches!(
+            BlorbMetadata::from_xml("<ifindex><story>"),
+            Err(BlorbError::InvalidMetadata(_))
+        ));
+    }
+}
@@ -1,20 +1,29 @@
+use std::io::{Read, Seek};
+
 use crate::chunk::{BlorbChunk, RawBlorbChunk};
 use crate::error::BlorbError;
-use crate::stream::BlorbStream;
+use crate::metadata::{BlorbMetadata, GameInfo};
+use crate::sound::{SoundInfo, SoundResource};
+use crate::stream::{read_error, BlorbStream};
 use crate::types::{BlorbType, ResourceType};
 
 /// A reader for blorb files
 #[derive(Debug)]
 pub struct BlorbReader {
     stream: BlorbStream,
-    ridx: Vec<RsrcIndex>,
+    declared_size: usize,
+    ridx: Vec<ResourceEntry>,
 }
 
-#[derive(Debug)]
-pub(crate) struct RsrcIndex {
-    usage: ResourceType,
-    id: usize,
-    offset: usize,
+/// An entry from the resource index (RIdx) chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceEntry {
+    /// What the resource is used for
+    pub usage: ResourceType,
+    /// The resource number
+    pub id: usize,
+    /// The offset of the resource's chunk from the start of the file
+    pub offset: usize,
 }
 
 /*
@@ -29,12 +38,46 @@ impl BlorbReader {
     /// Create a blorb file reader from a vec of bytes
     pub fn new(bytes: Vec<u8>) -> Result<Self, BlorbError> {
         let stream = BlorbStream::new(bytes);
+        let (declared_size, ridx) = Self::read_index(&stream)?;
+        Ok(Self {
+            stream,
+            declared_size,
+            ridx,
+        })
+    }
+
+    /// Create a blorb file reader which only reads the resource index up front. Resources
+    /// are read from the underlying reader when they are first requested
+    pub fn from_reader<R: Read + Seek + 'static>(mut reader: R) -> Result<Self, BlorbError> {
+        // the FORM header, the IFRS type, and the header of the RIdx chunk
+        let mut header = vec![0u8; 20];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| BlorbError::InvalidFileType)?;
+        let ridx_size = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+        (&mut reader)
+            .take(ridx_size as u64)
+            .read_to_end(&mut header)
+            .map_err(read_error)?;
 
+        let (declared_size, ridx) = Self::read_index(&BlorbStream::new(header))?;
+        let offsets = ridx.iter().map(|entry| entry.offset);
+        let stream = BlorbStream::from_reader(Box::new(reader), offsets);
+        Ok(Self {
+            stream,
+            declared_size,
+            ridx,
+        })
+    }
+
+    // read the blorb file header and its resource index, returning the size from the
+    // FORM header along with the index
+    fn read_index(stream: &BlorbStream) -> Result<(usize, Vec<ResourceEntry>), BlorbError> {
         if !stream.next_chunk_is(BlorbType::Form) {
             return Err(BlorbError::InvalidFileType);
         }
 
-        let _file_size = stream.read_chunk_size()?;
+        let file_size = stream.read_chunk_size()?;
         if !stream.next_chunk_is(BlorbType::Ifrs) {
             return Err(BlorbError::InvalidFileType);
         }
@@ -50,10 +93,10 @@ impl BlorbReader {
             let usage = stream.read_resource_type()?;
             let id = stream.read_chunk_size()?;
             let offset = stream.read_chunk_size()?;
-            ridx.push(RsrcIndex { usage, id, offset });
+            ridx.push(ResourceEntry { usage, id, offset });
         }
 
-        Ok(Self { stream, ridx })
+        Ok((file_size, ridx))
     }
 
     fn is_type(chunk: &Result<RawBlorbChunk, BlorbError>, blorb_type: BlorbType) -> bool {
@@ -64,15 +107,34 @@ impl BlorbReader {
         }
     }
 
-    pub(crate) fn find_chunk(&self, blorb_type: BlorbType) -> Result<RawBlorbChunk, BlorbError> {
+    /// Retrieve the first chunk of the given type
+    pub fn get_first_rsrc_by_type(
+        &self,
+        blorb_type: BlorbType,
+    ) -> Result<RawBlorbChunk<'_>, BlorbError> {
         self.iter()
             .find(|chunk| Self::is_type(chunk, blorb_type))
             .ok_or(BlorbError::ChunkNotFound)?
     }
 
+    /// Retrieve every chunk of the given type, in file order. Fails if any chunk in the
+    /// file can't be read, rather than leaving it out
+    pub fn get_all_rsrc_by_type(
+        &self,
+        blorb_type: BlorbType,
+    ) -> Result<Vec<RawBlorbChunk<'_>>, BlorbError> {
+        self.iter()
+            .filter(|chunk| {
+                chunk
+                    .as_ref()
+                    .map_or(true, |chunk| chunk.blorb_type == blorb_type)
+            })
+            .collect()
+    }
+
     /// Retrieve the image associated with the Frontispiece chunk
     pub fn get_frontispiece_image(&self) -> Option<RawBlorbChunk> {
-        let chunk = self.find_chunk(BlorbType::Fspc).ok()?;
+        let chunk = self.get_first_rsrc_by_type(BlorbType::Fspc).ok()?;
         if let BlorbChunk::Frontispiece(num) = (&chunk).try_into().ok()? {
             return self.get_resource(ResourceType::Pict, num).ok();
         }
@@ -80,15 +142,128 @@ impl BlorbReader {
         None
     }
 
+    /// Retrieve the textual description of a resource from the RDes chunk, for players
+    /// who cannot see or hear it
+    pub fn get_resource_description(&self, usage: ResourceType, id: usize) -> Option<String> {
+        let chunk = self.get_first_rsrc_by_type(BlorbType::Rdes).ok()?;
+        if let BlorbChunk::ResourceDescription(entries) = (&chunk).try_into().ok()? {
+            return entries
+                .into_iter()
+                .find(|entry| entry.usage == usage && entry.number == id)
+                .map(|entry| entry.text);
+        }
+
+        None
+    }
+
+    /// Retrieve the raw bytes of the Dbug chunk, which holds debugging information
+    /// for development tools
+    pub fn get_debug_info(&self) -> Option<&[u8]> {
+        self.get_first_rsrc_by_type(BlorbType::Dbug)
+            .ok()
+            .map(|chunk| chunk.bytes)
+    }
+
     /// Retrieve the game idenfier chunk
     pub fn get_game_identifier(&self) -> Option<BlorbChunk> {
-        if let Ok(chunk) = self.find_chunk(BlorbType::Ifhd) {
+        if let Ok(chunk) = self.get_first_rsrc_by_type(BlorbType::Ifhd) {
             (&chunk).try_into().ok()
         } else {
             None
         }
     }
 
+    /// Find out which virtual machine the executable resource is written for, either
+    /// BlorbType::Zcod or BlorbType::Glul
+    pub fn executable_type(&self) -> Result<BlorbType, BlorbError> {
+        let exec = self.get_first_resource(ResourceType::Executable)?;
+        match exec.blorb_type {
+            BlorbType::Zcod | BlorbType::Glul => Ok(exec.blorb_type),
+            _ => Err(exec.conversion_failed()),
+        }
+    }
+
+    /// Retrieve the release number from the RelN chunk
+    pub fn get_release_number(&self) -> Option<u16> {
+        let chunk = self.get_first_rsrc_by_type(BlorbType::Reln).ok()?;
+        match (&chunk).try_into() {
+            Ok(BlorbChunk::ReleaseNumber(release)) => Some(release),
+            _ => None,
+        }
+    }
+
+    /// Check that the Z-code executable matches the checksum in the game identifier chunk.
+    /// The checksum is the sum of the story file bytes from $40 up to the file length
+    /// given in the story header, modulo $10000
+    pub fn verify_game_identifier(&self) -> Result<bool, BlorbError> {
+        let chunk = self.get_first_rsrc_by_type(BlorbType::Ifhd)?;
+        let BlorbChunk::GameIdentifier { checksum, .. } = (&chunk).try_into()? else {
+            return Err(chunk.conversion_failed());
+        };
+
+        let exec = self
+            .get_resource(ResourceType::Executable, 0)
+            .map_err(|_| BlorbError::ChunkNotFound)?;
+        if exec.blorb_type != BlorbType::Zcod || exec.bytes.len() < 0x40 {
+            return Err(exec.conversion_failed());
+        }
+
+        let story = exec.bytes;
+        let scale = match story[0] {
+            1..=3 => 2,
+            4 | 5 => 4,
+            _ => 8,
+        };
+        let length = (story[0x1a] as usize) << 8 | story[0x1b] as usize;
+        let end = (length * scale).min(story.len());
+
+        let sum = story
+            .get(0x40..end)
+            .unwrap_or_default()
+            .iter()
+            .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+
+        Ok(sum == checksum)
+    }
+
+    /// Retrieve the game's bibliographic metadata from the IFmd chunk
+    pub fn get_metadata(&self) -> Result<BlorbMetadata, BlorbError> {
+        let chunk = self.get_first_rsrc_by_type(BlorbType::Ifmd)?;
+        BlorbMetadata::from_chunk(&chunk)
+    }
+
+    /// Gather the game's metadata and release number into a single summary. Anything
+    /// missing from the blorb file is left as None
+    pub fn game_info(&self) -> GameInfo {
+        let metadata = self.get_metadata().unwrap_or_default();
+        let release = self.get_release_number();
+
+        GameInfo {
+            ifid: metadata.ifid,
+            title: metadata.title,
+            author: metadata.author,
+            headline: metadata.headline,
+            description: metadata.description,
+            release,
+        }
+    }
+
+    /// Serialize the game's summary information as a JSON object
+    #[cfg(feature = "json")]
+    pub fn metadata_json(&self) -> String {
+        serde_json::to_string(&self.game_info()).unwrap_or_default()
+    }
+
+    /// Returns every entry in the resource index, in the order they appear in the file
+    pub fn resources(&self) -> Vec<ResourceEntry> {
+        self.ridx.clone()
+    }
+
+    /// Returns the number of entries in the resource index
+    pub fn resource_count(&self) -> usize {
+        self.ridx.len()
+    }
+
     /// Display a resource information entry
     pub fn dump_rsrc_usage(&self) {
         println!("{:?}", self.ridx);
@@ -99,22 +274,67 @@ impl BlorbReader {
         &self,
         usage: ResourceType,
         id: usize,
-    ) -> Result<RawBlorbChunk, BlorbError> {
+    ) -> Result<RawBlorbChunk<'_>, BlorbError> {
         if let Some(offset) = self.look_up_resource(usage, id) {
-            self.stream.seek(offset);
-            return Ok(self.stream.read_chunk()?.with_usage(usage));
+            return Ok(self.stream.read_chunk_at(offset)?.with_usage(usage));
         }
         Err(BlorbError::NonExistentResource(id))
     }
 
+    /// Retrieve the resource with the lowest ID for the given usage
+    pub fn get_first_resource(&self, usage: ResourceType) -> Result<RawBlorbChunk<'_>, BlorbError> {
+        let entry = self
+            .ridx
+            .iter()
+            .filter(|entry| entry.usage == usage)
+            .min_by_key(|entry| entry.id)
+            .ok_or(BlorbError::NoResourcesOfType(usage))?;
+        Ok(self.stream.read_chunk_at(entry.offset)?.with_usage(usage))
+    }
+
+    /// Retrieve the chunk whose header starts at the given offset in the file, such as the
+    /// offset of a resource index entry. This skips looking the resource up in the index
+    pub fn get_chunk_at(&self, offset: usize) -> Result<RawBlorbChunk<'_>, BlorbError> {
+        self.stream.read_chunk_at(offset)
+    }
+
+    /// Retrieve a copy of a resource's data along with its chunk type, so it can outlive
+    /// the reader or be sent to another thread
+    pub fn get_resource_owned(
+        &self,
+        usage: ResourceType,
+        id: usize,
+    ) -> Result<(BlorbType, Vec<u8>), BlorbError> {
+        let chunk = self.get_resource(usage, id)?;
+        Ok((chunk.blorb_type, chunk.bytes.to_vec()))
+    }
+
+    /// Get the width and height of a picture resource from its PNG or JPEG header
+    pub fn get_image_size(&self, id: usize) -> Result<(u32, u32), BlorbError> {
+        let pict = self.get_resource(ResourceType::Pict, id)?;
+        png_size(pict.bytes)
+            .or_else(|| jpeg_size(pict.bytes))
+            .ok_or(BlorbError::UnsupportedImageFormat)
+    }
+
+    /// Get a sound resource along with the format it is stored in
+    pub fn get_sound(&self, id: usize) -> Result<SoundResource, BlorbError> {
+        let sound = self.get_resource(ResourceType::Sound, id)?;
+        SoundResource::try_from(&sound)
+    }
+
+    /// Get the format of a sound resource, and the sample rate, channels and bit depth of
+    /// AIFF sounds. Returns None if there is no such sound or its format is not recognized
+    pub fn sound_info(&self, id: usize) -> Option<SoundInfo> {
+        self.get_sound(id).ok().map(|sound| sound.info())
+    }
+
     /// Get a text type data resource converted from Latin-1
     pub fn get_latin1_text_resource(&self, id: usize) -> Result<String, BlorbError> {
         let offset = self
             .look_up_resource(ResourceType::Data, id)
             .ok_or(BlorbError::NonExistentResource(id))?;
-        self.stream.seek(offset);
-
-        let chunk = self.stream.read_chunk()?;
+        let chunk = self.stream.read_chunk_at(offset)?;
         Ok(chunk.bytes.iter().map(|&ch| ch as char).collect())
     }
 
@@ -123,11 +343,9 @@ impl BlorbReader {
         let offset = self
             .look_up_resource(ResourceType::Data, id)
             .ok_or(BlorbError::NonExistentResource(id))?;
-        self.stream.seek(offset);
-
-        let chunk = self.stream.read_chunk()?;
+        let chunk = self.stream.read_chunk_at(offset)?;
         Ok(std::str::from_utf8(chunk.bytes)
-            .map_err(|_| BlorbError::ConversionFailed)?
+            .map_err(|_| chunk.conversion_failed())?
             .to_string())
     }
 
@@ -142,14 +360,55 @@ impl BlorbReader {
     }
 
     pub(crate) fn read_next_chunk(&self) -> Result<RawBlorbChunk, BlorbError> {
+        let offset = self.stream.get_offset();
         let blorb_type = self.stream.read_chunk_type()?;
         let chunk_size = self.stream.read_chunk_size()?;
         Ok(RawBlorbChunk::new(
             blorb_type,
-            self.stream.get_next_chunk(chunk_size),
+            offset,
+            self.stream.get_next_chunk(chunk_size)?,
         ))
     }
 
+    /// The size of the file's contents as given in its FORM header. This does not include
+    /// the 8 bytes of the header itself
+    pub fn declared_size(&self) -> usize {
+        self.declared_size
+    }
+
+    /// Check that the file is as long as its FORM header says it is. A file which is too
+    /// short was most likely truncated
+    pub fn validate_size(&self) -> Result<(), BlorbError> {
+        let declared = self.declared_size + 8;
+        let actual = self.stream.file_len();
+        if declared == actual {
+            Ok(())
+        } else {
+            Err(BlorbError::SizeMismatch { declared, actual })
+        }
+    }
+
+    /// Strictly check the structure of the blorb file. This reports problems that the
+    /// reader otherwise tolerates, such as a non-zero padding byte after an odd-length chunk.
+    pub fn validate(&self) -> Result<(), BlorbError> {
+        self.stream.seek(12);
+        loop {
+            match self.stream.read_chunk_type() {
+                Ok(_) => {}
+                Err(BlorbError::EndOfFile) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+
+            let chunk_size = self.stream.read_chunk_size()?;
+            let pad_offset = self.stream.get_offset() + chunk_size;
+            self.stream.get_next_chunk(chunk_size)?;
+
+            if chunk_size % 2 == 1 && self.stream.get_byte(pad_offset).unwrap_or(0) != 0 {
+                return Err(BlorbError::InvalidPadding(pad_offset));
+            }
+        }
+    }
+
     /// Returns an iterator which walks all of the chunks in a blorb file
     pub fn iter(&self) -> BlorbIterator {
         self.stream.seek(12);
@@ -157,6 +416,50 @@ impl BlorbReader {
     }
 }
 
+// The IHDR chunk always comes first, right after the 8-byte signature
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if !bytes.starts_with(b"\x89PNG\r\n\x1a\n") || bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+// Walk the JPEG segments until a start-of-frame marker, which holds the image size
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if !bytes.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    let mut offset = 2;
+    loop {
+        if *bytes.get(offset)? != 0xff {
+            return None;
+        }
+        let marker = *bytes.get(offset + 1)?;
+        match marker {
+            // padding before a marker
+            0xff => offset += 1,
+            // markers without a length field
+            0x01 | 0xd0..=0xd7 => offset += 2,
+            // SOF0 (baseline) through SOF2 (progressive)
+            0xc0..=0xc2 => {
+                let frame = bytes.get(offset + 5..offset + 9)?;
+                let height = (frame[0] as u32) << 8 | frame[1] as u32;
+                let width = (frame[2] as u32) << 8 | frame[3] as u32;
+                return Some((width, height));
+            }
+            // end of image, or start of the compressed data, without seeing a frame
+            0xd9 | 0xda => return None,
+            _ => {
+                let len = bytes.get(offset + 2..offset + 4)?;
+                offset += 2 + ((len[0] as usize) << 8 | len[1] as usize);
+            }
+        }
+    }
+}
+
 /// An iterator over all the chunks in a blorb file
 pub struct BlorbIterator<'a> {
     blorb: &'a BlorbReader,
@@ -194,3 +497,565 @@ impl<'a> Iterator for BlorbTypeIterator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sound::SoundFormat;
+    use crate::testing::BlorbBuilder;
+
+    // no resources, and a single odd-length AUTH chunk
+    fn blorb_with_auth() -> BlorbBuilder {
+        BlorbBuilder::new().chunk(b"AUTH", b"abc")
+    }
+
+    fn blorb_with_padding(pad: u8) -> Vec<u8> {
+        blorb_with_auth().padding(pad).build()
+    }
+
+    #[test]
+    fn same_id_with_different_usages_are_different_resources() {
+        let blorb = BlorbReader::new(vec![
+            0x46, 0x4f, 0x52, 0x4d, // "FORM"
+            0, 0, 0, 64, // file length
+            0x49, 0x46, 0x52, 0x53, // "IFRS"
+            0x52, 0x49, 0x64, 0x78, // "RIdx"
+            0, 0, 0, 28, // chunk length
+            0, 0, 0, 2, // two resources
+            0x50, 0x69, 0x63, 0x74, 0, 0, 0, 1, 0, 0, 0, 48, // Pict 1 @ 48
+            0x44, 0x61, 0x74, 0x61, 0, 0, 0, 1, 0, 0, 0, 60, // Data 1 @ 60
+            0x50, 0x4e, 0x47, 0x20, // "PNG "
+            0, 0, 0, 4, // chunk length
+            1, 2, 3, 4, // image data
+            0x54, 0x45, 0x58, 0x54, // "TEXT"
+            0, 0, 0, 4, // chunk length
+            0x61, 0x62, 0x63, 0x64, // "abcd"
+        ])
+        .expect("could not read blorb");
+
+        assert_eq!(2, blorb.resource_count());
+        assert_eq!(
+            vec![
+                ResourceEntry {
+                    usage: ResourceType::Pict,
+                    id: 1,
+                    offset: 48
+                },
+                ResourceEntry {
+                    usage: ResourceType::Data,
+                    id: 1,
+                    offset: 60
+                },
+            ],
+            blorb.resources()
+        );
+
+        let pict = blorb
+            .get_resource(ResourceType::Pict, 1)
+            .expect("missing pict");
+        assert_eq!(BlorbType::Png, pict.blorb_type);
+        assert_eq!(&[1, 2, 3, 4], pict.bytes);
+
+        let data = blorb
+            .get_resource(ResourceType::Data, 1)
+            .expect("missing data");
+        assert_eq!(BlorbType::Text, data.blorb_type);
+        assert_eq!(b"abcd", data.bytes);
+    }
+
+    #[test]
+    fn owned_resources_outlive_the_reader() {
+        let blorb = BlorbReader::new(
+            BlorbBuilder::new()
+                .resource(ResourceType::Pict, 1, b"PNG ", &[1, 2, 3, 4])
+                .build(),
+        )
+        .expect("could not read blorb");
+
+        let (blorb_type, bytes) = blorb
+            .get_resource_owned(ResourceType::Pict, 1)
+            .expect("missing pict");
+        drop(blorb);
+        assert_eq!(BlorbType::Png, blorb_type);
+        assert_eq!(vec![1, 2, 3, 4], bytes);
+    }
+
+    #[test]
+    fn can_read_resources_lazily_from_a_reader() {
+        let bytes = BlorbBuilder::new()
+            .chunk(b"AUTH", b"ab")
+            .resource(ResourceType::Sound, 3, b"OGGV", &[5, 6, 7, 8])
+            .build();
+        let blorb =
+            BlorbReader::from_reader(std::io::Cursor::new(bytes)).expect("could not read blorb");
+        assert_eq!(1, blorb.resource_count());
+
+        let sound = blorb
+            .get_resource(ResourceType::Sound, 3)
+            .expect("missing sound");
+        assert_eq!(BlorbType::Oggv, sound.blorb_type);
+        assert_eq!(&[5, 6, 7, 8], sound.bytes);
+
+        // walking every chunk still works, by pulling in the rest of the file
+        let auth = blorb
+            .get_first_rsrc_by_type(BlorbType::Auth)
+            .expect("missing AUTH chunk");
+        assert_eq!(b"ab", auth.bytes);
+    }
+
+    #[test]
+    fn can_get_the_executable_type() {
+        let blorb = BlorbReader::new(blorb_with_game(b"ZCOD", 10)).expect("could not read blorb");
+        assert_eq!(Ok(BlorbType::Zcod), blorb.executable_type());
+
+        let blorb = BlorbReader::new(blorb_with_game(b"GLUL", 10)).expect("could not read blorb");
+        assert_eq!(Ok(BlorbType::Glul), blorb.executable_type());
+
+        let blorb = BlorbReader::new(blorb_with_game(b"BINA", 10)).expect("could not read blorb");
+        assert!(blorb.executable_type().unwrap_err().is_conversion_failure());
+
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(
+            Err(BlorbError::NoResourcesOfType(ResourceType::Executable)),
+            blorb.executable_type()
+        );
+    }
+
+    #[test]
+    fn can_get_the_release_number() {
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(None, blorb.get_release_number());
+
+        let bytes = blorb_with_auth().chunk(b"RelN", &[1, 2]).build();
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+        assert_eq!(Some(0x102), blorb.get_release_number());
+
+        // a malformed chunk is ignored rather than causing a panic
+        let bytes = blorb_with_auth().chunk(b"RelN", &[1]).build();
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+        assert_eq!(None, blorb.get_release_number());
+    }
+
+    #[test]
+    fn can_detect_a_truncated_file() {
+        let bytes = blorb_with_padding(0);
+        let blorb = BlorbReader::new(bytes.clone()).expect("could not read blorb");
+        assert_eq!(28, blorb.declared_size());
+        assert_eq!(Ok(()), blorb.validate_size());
+
+        let blorb = BlorbReader::new(bytes[..35].to_vec()).expect("could not read blorb");
+        assert_eq!(
+            Err(BlorbError::SizeMismatch {
+                declared: 36,
+                actual: 35
+            }),
+            blorb.validate_size()
+        );
+
+        let blorb = BlorbReader::from_reader(std::io::Cursor::new(bytes[..33].to_vec()))
+            .expect("could not read blorb");
+        assert_eq!(
+            Err(BlorbError::SizeMismatch {
+                declared: 36,
+                actual: 33
+            }),
+            blorb.validate_size()
+        );
+    }
+
+    #[test]
+    fn reader_must_contain_a_blorb_file() {
+        let result = BlorbReader::from_reader(std::io::Cursor::new(b"not a blorb".to_vec()));
+        assert_eq!(BlorbError::InvalidFileType, result.unwrap_err());
+    }
+
+    fn blorb_with_game(type_code: &[u8; 4], checksum: u16) -> Vec<u8> {
+        let mut ifhd = vec![0, 1]; // release number
+        ifhd.extend_from_slice(b"230101"); // serial number
+        ifhd.extend_from_slice(&checksum.to_be_bytes());
+        ifhd.extend_from_slice(&[0, 0, 0]); // pc
+
+        let mut story = vec![0u8; 0x44];
+        story[0] = 3; // version 3 stores the length in words
+        story[0x1b] = 0x22;
+        story[0x40..].copy_from_slice(&[1, 2, 3, 4]);
+
+        BlorbBuilder::new()
+            .chunk(b"IFhd", &ifhd)
+            .resource(ResourceType::Executable, 0, type_code, &story)
+            .build()
+    }
+
+    #[test]
+    fn can_verify_game_checksum() {
+        let blorb = BlorbReader::new(blorb_with_game(b"ZCOD", 10)).expect("could not read blorb");
+        assert_eq!(Ok(true), blorb.verify_game_identifier());
+
+        let blorb = BlorbReader::new(blorb_with_game(b"ZCOD", 11)).expect("could not read blorb");
+        assert_eq!(Ok(false), blorb.verify_game_identifier());
+    }
+
+    #[test]
+    fn cannot_verify_game_without_identifier() {
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(
+            Err(BlorbError::ChunkNotFound),
+            blorb.verify_game_identifier()
+        );
+    }
+
+    #[test]
+    fn zero_size_data_resource_is_empty() {
+        let blorb = BlorbReader::new(
+            BlorbBuilder::new()
+                .resource(ResourceType::Data, 2, b"TEXT", b"")
+                .build(),
+        )
+        .expect("could not read blorb");
+
+        let data = blorb
+            .get_resource(ResourceType::Data, 2)
+            .expect("missing data");
+        assert!(data.bytes.is_empty());
+        assert_eq!(Ok(String::new()), blorb.get_utf8_text_resource(2));
+
+        let chunks = blorb
+            .iter()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("bad chunk");
+        assert_eq!(2, chunks.len());
+        assert!(chunks[1].bytes.is_empty());
+        assert_eq!(Ok(()), blorb.validate());
+    }
+
+    fn blorb_with_picture(image: &[u8]) -> Vec<u8> {
+        BlorbBuilder::new()
+            .resource(ResourceType::Pict, 1, b"PNG ", image)
+            .build()
+    }
+
+    #[test]
+    fn can_get_png_image_size() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 1, 0x40, 0, 0, 0, 0xc8]);
+        let blorb = BlorbReader::new(blorb_with_picture(&png)).expect("could not read blorb");
+        assert_eq!(Ok((320, 200)), blorb.get_image_size(1));
+    }
+
+    #[test]
+    fn can_get_jpeg_image_size() {
+        let jpeg = [
+            0xff, 0xd8, // start of image
+            0xff, 0xe0, 0, 4, 0, 0, // APP0 segment
+            0xff, 0xc2, 0, 11, 8, 0, 0x30, 0, 0x40, 3, 0, 0, 0, // SOF2: 64x48
+        ];
+        let blorb = BlorbReader::new(blorb_with_picture(&jpeg)).expect("could not read blorb");
+        assert_eq!(Ok((64, 48)), blorb.get_image_size(1));
+    }
+
+    #[test]
+    fn unknown_image_format_is_unsupported() {
+        let blorb = BlorbReader::new(blorb_with_picture(b"GIF89a\x01\0\x01\0"))
+            .expect("could not read blorb");
+        assert_eq!(
+            Err(BlorbError::UnsupportedImageFormat),
+            blorb.get_image_size(1)
+        );
+        assert_eq!(
+            Err(BlorbError::NonExistentResource(2)),
+            blorb.get_image_size(2)
+        );
+    }
+
+    fn blorb_with_sound(type_code: &[u8; 4], bytes: &[u8]) -> Vec<u8> {
+        BlorbBuilder::new()
+            .resource(ResourceType::Sound, 5, type_code, bytes)
+            .build()
+    }
+
+    #[test]
+    fn can_get_sounds_by_format() {
+        let blorb = BlorbReader::new(blorb_with_sound(b"FORM", b"AIFFxy")).expect("bad blorb");
+        assert_eq!(
+            Ok(SoundResource::Aiff(b"FORM\0\0\0\x06AIFFxy".to_vec())),
+            blorb.get_sound(5)
+        );
+
+        let blorb = BlorbReader::new(blorb_with_sound(b"OGGV", b"OggS")).expect("bad blorb");
+        assert_eq!(Ok(SoundResource::Ogg(b"OggS".to_vec())), blorb.get_sound(5));
+
+        let blorb = BlorbReader::new(blorb_with_sound(b"MOD ", b"md")).expect("bad blorb");
+        let sound = blorb.get_sound(5).expect("missing sound");
+        assert_eq!(SoundResource::Mod(b"md".to_vec()), sound);
+        assert_eq!(b"md", sound.bytes());
+
+        let blorb = BlorbReader::new(blorb_with_sound(b"Song", b"sg")).expect("bad blorb");
+        assert_eq!(Ok(SoundResource::Song(b"sg".to_vec())), blorb.get_sound(5));
+        assert_eq!(Err(BlorbError::NonExistentResource(6)), blorb.get_sound(6));
+    }
+
+    #[test]
+    fn can_read_aiff_sample_metadata() {
+        let mut aiff = b"AIFF".to_vec();
+        aiff.extend_from_slice(b"NAME\0\0\0\x03abc\0"); // odd length, padded
+        aiff.extend_from_slice(b"COMM\0\0\0\x12");
+        aiff.extend_from_slice(&[0, 2]); // channels
+        aiff.extend_from_slice(&[0, 0, 0x10, 0]); // sample frames
+        aiff.extend_from_slice(&[0, 16]); // bits per sample
+        aiff.extend_from_slice(&[0x40, 0x0d, 0xac, 0x44, 0, 0, 0, 0, 0, 0]); // 22050
+        aiff.extend_from_slice(b"SSND\0\0\0\0");
+
+        let blorb = BlorbReader::new(blorb_with_sound(b"FORM", &aiff)).expect("bad blorb");
+        assert_eq!(
+            Some(SoundInfo {
+                format: SoundFormat::Aiff,
+                sample_rate: Some(22050),
+                channels: Some(2),
+                bits_per_sample: Some(16),
+            }),
+            blorb.sound_info(5)
+        );
+        assert_eq!(None, blorb.sound_info(6));
+
+        let blorb = BlorbReader::new(blorb_with_sound(b"OGGV", b"OggS")).expect("bad blorb");
+        let info = blorb.sound_info(5).expect("missing sound info");
+        assert_eq!(SoundFormat::Ogg, info.format);
+        assert_eq!(None, info.sample_rate);
+
+        let blorb =
+            BlorbReader::new(blorb_with_sound(b"FORM", b"IFRS")).expect("could not read blorb");
+        assert_eq!(None, blorb.sound_info(5));
+    }
+
+    #[test]
+    fn non_aiff_form_is_an_unsupported_sound() {
+        let blorb =
+            BlorbReader::new(blorb_with_sound(b"FORM", b"IFRS")).expect("could not read blorb");
+        assert_eq!(Err(BlorbError::UnsupportedSoundFormat), blorb.get_sound(5));
+        let chunk = blorb
+            .get_resource(ResourceType::Sound, 5)
+            .expect("missing sound");
+        assert_eq!(36, chunk.offset());
+    }
+
+    #[test]
+    fn first_resource_is_the_one_with_the_lowest_id() {
+        let blorb = BlorbReader::new(
+            BlorbBuilder::new()
+                .resource(ResourceType::Pict, 9, b"PNG ", b"nine")
+                .resource(ResourceType::Pict, 4, b"PNG ", b"four")
+                .resource(ResourceType::Data, 1, b"BINA", b"")
+                .build(),
+        )
+        .expect("could not read blorb");
+
+        let pict = blorb
+            .get_first_resource(ResourceType::Pict)
+            .expect("missing picture");
+        assert_eq!(b"four", pict.bytes);
+        assert_eq!(
+            Err(BlorbError::NoResourcesOfType(ResourceType::Sound)),
+            blorb.get_first_resource(ResourceType::Sound).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn empty_resource_index_has_no_entries() {
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(0, blorb.resource_count());
+        assert!(blorb.resources().is_empty());
+    }
+
+    #[test]
+    fn resource_count_matches_the_number_of_resources() {
+        let blorb = BlorbReader::new(
+            BlorbBuilder::new()
+                .resource(ResourceType::Pict, 1, b"PNG ", &[1, 2, 3, 4])
+                .resource(ResourceType::Sound, 3, b"OGGV", b"ogg")
+                .resource(ResourceType::Data, 5, b"TEXT", b"text")
+                .build(),
+        )
+        .expect("could not read blorb");
+        assert_eq!(3, blorb.resource_count());
+    }
+
+    #[test]
+    fn can_find_chunks_by_type() {
+        let bytes = blorb_with_auth().chunk(b"AUTH", b"xy").build();
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+
+        let first = blorb
+            .get_first_rsrc_by_type(BlorbType::Auth)
+            .expect("missing AUTH chunk");
+        assert_eq!(b"abc", first.bytes);
+
+        let all = blorb
+            .get_all_rsrc_by_type(BlorbType::Auth)
+            .expect("bad chunk");
+        assert_eq!(2, all.len());
+        assert_eq!(b"xy", all[1].bytes);
+
+        assert!(matches!(
+            blorb.get_first_rsrc_by_type(BlorbType::Png),
+            Err(BlorbError::ChunkNotFound)
+        ));
+        assert_eq!(
+            Ok(0),
+            blorb
+                .get_all_rsrc_by_type(BlorbType::Png)
+                .map(|all| all.len())
+        );
+
+        // a chunk that can't be read is an error, even if it isn't of the requested type
+        let mut bytes = blorb_with_auth().chunk(b"ANNO", b"xy").build();
+        bytes.pop();
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+        assert_eq!(
+            Err(BlorbError::TruncatedChunk),
+            blorb
+                .get_all_rsrc_by_type(BlorbType::Auth)
+                .map(|all| all.len())
+        );
+    }
+
+    #[test]
+    fn padding_is_skipped_before_the_last_chunk() {
+        // the final odd-length chunk may or may not be followed by its padding byte
+        for builder in [
+            blorb_with_auth().chunk(b"ANNO", b"x").unpadded_end(),
+            blorb_with_auth().chunk(b"ANNO", b"x"),
+        ] {
+            let blorb = BlorbReader::new(builder.build()).expect("could not read blorb");
+
+            let chunks = blorb
+                .iter()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("bad chunk");
+            assert_eq!(3, chunks.len());
+            assert_eq!(BlorbType::Auth, chunks[1].blorb_type);
+            assert_eq!(b"abc", chunks[1].bytes);
+            assert_eq!(BlorbType::Anno, chunks[2].blorb_type);
+            assert_eq!(b"x", chunks[2].bytes);
+            assert_eq!(Ok(()), blorb.validate());
+        }
+    }
+
+    #[test]
+    fn truncated_files_are_an_error() {
+        let bytes = blorb_with_padding(0);
+        for len in 0..bytes.len() {
+            let _ = BlorbReader::new(bytes[..len].to_vec()).map(|blorb| blorb.validate());
+        }
+
+        let mut bytes = bytes[..24].to_vec();
+        bytes[23] = 1; // one resource, but the index entry is cut short
+        assert_eq!(
+            BlorbError::TruncatedChunk,
+            BlorbReader::new(bytes).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn can_read_metadata_chunk() {
+        let xml =
+            b"<ifindex><story><bibliographic><title>Zork</title></bibliographic></story></ifindex>";
+        let bytes = blorb_with_auth().chunk(b"IFmd", xml).build();
+
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+        let metadata = blorb.get_metadata().expect("missing metadata");
+        assert_eq!(metadata.title.as_deref(), Some("Zork"));
+        assert!(metadata.author.is_none());
+    }
+
+    #[test]
+    fn game_info_combines_metadata_and_release() {
+        let xml = b"<ifindex><story><bibliographic><title>Zork</title><author>Infocom</author></bibliographic></story></ifindex>";
+        let bytes = blorb_with_auth()
+            .chunk(b"IFmd", xml)
+            .chunk(b"RelN", &[0, 0x58])
+            .build();
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+
+        let info = blorb.game_info();
+        assert_eq!(info.title.as_deref(), Some("Zork"));
+        assert_eq!(info.author.as_deref(), Some("Infocom"));
+        assert_eq!(info.release, Some(88));
+
+        #[cfg(feature = "json")]
+        {
+            let json = blorb.metadata_json();
+            assert!(json.contains(r#""title":"Zork""#));
+            assert!(json.contains(r#""author":"Infocom""#));
+        }
+    }
+
+    #[test]
+    fn resources_can_be_described() {
+        let mut rdes = vec![0, 0, 0, 2];
+        rdes.extend_from_slice(b"Pict\0\0\0\x01\0\0\0\x04lamp");
+        rdes.extend_from_slice(b"Snd \0\0\0\x01\0\0\0\x05crash");
+        let blorb = BlorbReader::new(BlorbBuilder::new().chunk(b"RDes", &rdes).build())
+            .expect("could not read blorb");
+
+        assert_eq!(
+            Some("lamp".to_string()),
+            blorb.get_resource_description(ResourceType::Pict, 1)
+        );
+        assert_eq!(
+            Some("crash".to_string()),
+            blorb.get_resource_description(ResourceType::Sound, 1)
+        );
+        assert_eq!(None, blorb.get_resource_description(ResourceType::Pict, 2));
+
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(None, blorb.get_resource_description(ResourceType::Pict, 1));
+    }
+
+    #[test]
+    fn can_read_a_chunk_at_a_resource_offset() {
+        let bytes = BlorbBuilder::new()
+            .resource(ResourceType::Data, 1, b"BINA", b"abc")
+            .resource(ResourceType::Data, 2, b"TEXT", b"de")
+            .build();
+
+        for blorb in [
+            BlorbReader::new(bytes.clone()).expect("could not read blorb"),
+            BlorbReader::from_reader(std::io::Cursor::new(bytes)).expect("could not read blorb"),
+        ] {
+            let offset = blorb.resources()[1].offset;
+            let chunk = blorb.get_chunk_at(offset).expect("missing chunk");
+            assert_eq!(BlorbType::Text, chunk.blorb_type);
+            assert_eq!(b"de", chunk.bytes);
+            assert_eq!(offset, chunk.offset());
+        }
+    }
+
+    #[test]
+    fn debug_info_is_returned_as_raw_bytes() {
+        let debug = [0xde, 0xbf, 0x00, 0x00, 0x01, 0x02];
+        let blorb = BlorbReader::new(BlorbBuilder::new().chunk(b"Dbug", &debug).build())
+            .expect("could not read blorb");
+        assert_eq!(Some(&debug[..]), blorb.get_debug_info());
+
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(None, blorb.get_debug_info());
+    }
+
+    #[test]
+    fn missing_metadata_chunk_is_not_found() {
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(Err(BlorbError::ChunkNotFound), blorb.get_metadata());
+    }
+
+    #[test]
+    fn zero_padding_is_valid() {
+        let blorb = BlorbReader::new(blorb_with_padding(0)).expect("could not read blorb");
+        assert_eq!(Ok(()), blorb.validate());
+    }
+
+    #[test]
+    fn non_zero_padding_is_flagged() {
+        let blorb = BlorbReader::new(blorb_with_padding(0xff)).expect("could not read blorb");
+        assert_eq!(Err(BlorbError::InvalidPadding(35)), blorb.validate());
+    }
+}
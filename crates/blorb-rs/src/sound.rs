@@ -0,0 +1,131 @@
+use crate::chunk::RawBlorbChunk;
+use crate::error::BlorbError;
+use crate::types::BlorbType;
+
+/// A sound resource, tagged with the format of its data (Blorb Spec section 4)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoundResource {
+    /// An AIFF sound, including its "FORM" header
+    Aiff(Vec<u8>),
+    /// A MOD music file
+    Mod(Vec<u8>),
+    /// An Ogg Vorbis sound
+    Ogg(Vec<u8>),
+    /// A SONG file, which plays AIFF samples from the blorb's Data resources
+    Song(Vec<u8>),
+}
+
+/// The format a sound resource is stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundFormat {
+    /// AIFF sampled sound
+    Aiff,
+    /// MOD music
+    Mod,
+    /// Ogg Vorbis
+    Ogg,
+    /// SONG music
+    Song,
+}
+
+/// The format of a sound resource, and what is known about its samples. Only AIFF sounds
+/// describe their samples, in the COMM chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoundInfo {
+    /// The format the sound is stored in
+    pub format: SoundFormat,
+    /// Samples per second
+    pub sample_rate: Option<u32>,
+    /// The number of channels
+    pub channels: Option<u16>,
+    /// The number of bits in each sample
+    pub bits_per_sample: Option<u16>,
+}
+
+impl SoundResource {
+    /// The raw sound data
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Aiff(bytes) | Self::Mod(bytes) | Self::Ogg(bytes) | Self::Song(bytes) => bytes,
+        }
+    }
+
+    /// The format the sound is stored in
+    pub fn format(&self) -> SoundFormat {
+        match self {
+            Self::Aiff(_) => SoundFormat::Aiff,
+            Self::Mod(_) => SoundFormat::Mod,
+            Self::Ogg(_) => SoundFormat::Ogg,
+            Self::Song(_) => SoundFormat::Song,
+        }
+    }
+
+    /// The format of the sound, along with its sample rate, channels and bit depth if
+    /// they are known
+    pub fn info(&self) -> SoundInfo {
+        let comm = match self {
+            Self::Aiff(bytes) => aiff_comm(bytes),
+            _ => None,
+        };
+
+        SoundInfo {
+            format: self.format(),
+            sample_rate: comm.map(|(_, _, rate)| rate),
+            channels: comm.map(|(channels, _, _)| channels),
+            bits_per_sample: comm.map(|(_, bits, _)| bits),
+        }
+    }
+}
+
+// find the COMM chunk in an AIFF file, and return its channel count, sample size, and
+// sample rate
+fn aiff_comm(bytes: &[u8]) -> Option<(u16, u16, u32)> {
+    let mut offset = 12;
+    while let Some(header) = bytes.get(offset..offset + 8) {
+        let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let data = bytes.get(offset + 8..offset + 8 + len)?;
+        if &header[0..4] == b"COMM" {
+            if data.len() < 18 {
+                return None;
+            }
+            let channels = u16::from_be_bytes([data[0], data[1]]);
+            let bits = u16::from_be_bytes([data[6], data[7]]);
+            return Some((channels, bits, extended_to_u32(&data[8..18])));
+        }
+        // chunks are padded to an even length
+        offset += 8 + len + (len & 1);
+    }
+
+    None
+}
+
+// the sample rate is an 80-bit IEEE 754 extended precision number: a sign bit, a 15 bit
+// exponent, and a 64 bit mantissa with an explicit leading one
+fn extended_to_u32(bytes: &[u8]) -> u32 {
+    let exponent = (u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7fff) as i32;
+    let mut mantissa = [0; 8];
+    mantissa.copy_from_slice(&bytes[2..10]);
+    let mantissa = u64::from_be_bytes(mantissa);
+    if bytes[0] & 0x80 != 0 || mantissa == 0 {
+        return 0;
+    }
+
+    let value = mantissa as f64 * 2f64.powi(exponent - 16383 - 63);
+    value.round().min(u32::MAX as f64) as u32
+}
+
+impl TryFrom<&RawBlorbChunk<'_>> for SoundResource {
+    type Error = BlorbError;
+
+    fn try_from(chunk: &RawBlorbChunk<'_>) -> Result<Self, Self::Error> {
+        let bytes = chunk.bytes.to_vec();
+        match chunk.blorb_type {
+            // an AIFF file is stored whole, so the form type follows the FORM header
+            BlorbType::Form if chunk.bytes.get(8..12) == Some(b"AIFF") => Ok(Self::Aiff(bytes)),
+            BlorbType::Mod => Ok(Self::Mod(bytes)),
+            BlorbType::Oggv => Ok(Self::Ogg(bytes)),
+            BlorbType::Song => Ok(Self::Song(bytes)),
+            _ => Err(BlorbError::UnsupportedSoundFormat),
+        }
+    }
+}
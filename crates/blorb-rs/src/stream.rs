@@ -1,4 +1,7 @@
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, SeekFrom};
 
 use crate::{
     chunk::RawBlorbChunk,
@@ -6,43 +9,121 @@ use crate::{
     types::{BlorbType, ResourceType},
 };
 
-#[derive(Debug)]
+pub(crate) trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
 pub(crate) struct BlorbStream {
-    bytes: Vec<u8>,
+    // the whole file; filled in up front for in-memory blorbs, or on first use otherwise
+    bytes: OnceCell<Vec<u8>>,
+    source: Option<RefCell<Box<dyn ReadSeek>>>,
+    // chunks loaded on demand from the source, keyed by their offset in the file
+    chunks: HashMap<usize, OnceCell<Vec<u8>>>,
     cursor: RefCell<usize>,
 }
 
+impl Debug for BlorbStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("BlorbStream")
+            .field("loaded", &self.bytes.get().map(|bytes| bytes.len()))
+            .field("lazy", &self.source.is_some())
+            .field("cursor", &self.cursor)
+            .finish()
+    }
+}
+
 impl BlorbStream {
     pub fn new(bytes: Vec<u8>) -> Self {
         Self {
-            bytes,
+            bytes: OnceCell::from(bytes),
+            source: None,
+            chunks: HashMap::new(),
             cursor: RefCell::new(0),
         }
     }
 
-    pub fn get_next_chunk(&self, size: usize) -> &[u8] {
-        let offset = *self.cursor.borrow();
-        *self.cursor.borrow_mut() += size + (size % 2);
-        &(self.bytes[offset..offset + size])
+    /// Create a stream which reads the chunks at the given offsets only when they are asked for
+    pub fn from_reader(source: Box<dyn ReadSeek>, offsets: impl Iterator<Item = usize>) -> Self {
+        Self {
+            bytes: OnceCell::new(),
+            source: Some(RefCell::new(source)),
+            chunks: offsets.map(|offset| (offset, OnceCell::new())).collect(),
+            cursor: RefCell::new(0),
+        }
     }
 
-    pub fn read_chunk(&self) -> Result<RawBlorbChunk, BlorbError> {
-        let offset = *self.cursor.borrow();
-        let blorb_type = self.read_chunk_type()?;
-        let size = self.read_chunk_size()?;
+    fn data(&self) -> &[u8] {
+        self.bytes.get_or_init(|| {
+            let mut bytes = Vec::new();
+            if let Some(source) = &self.source {
+                let mut source = source.borrow_mut();
+                // a failed read leaves a short buffer, which shows up as TruncatedChunk later
+                if source.rewind().is_ok() {
+                    let _ = source.read_to_end(&mut bytes);
+                }
+            }
+            bytes
+        })
+    }
 
-        // "FORM" type chunks are raw storage in the blorb file, so we need
-        // to return the whole thing instead of just the chunk's data
-        let start_pos = if blorb_type == BlorbType::Form {
-            offset
-        } else {
-            offset + 8
-        };
+    /// The length of the whole file, found without reading it all in if possible
+    pub fn file_len(&self) -> usize {
+        if let (None, Some(source)) = (self.bytes.get(), &self.source) {
+            if let Ok(len) = source.borrow_mut().seek(SeekFrom::End(0)) {
+                return len as usize;
+            }
+        }
+        self.data().len()
+    }
 
-        Ok(RawBlorbChunk::new(
-            blorb_type,
-            &(self.bytes[start_pos..offset + 8 + size]),
-        ))
+    // returns the bytes in offset..offset+size, or an error if that extends past the end
+    fn get_range(&self, offset: usize, size: usize) -> Result<&[u8], BlorbError> {
+        get_range(self.data(), offset, size)
+    }
+
+    // read a single chunk, header included, from the source
+    fn load_chunk(&self, offset: usize) -> Result<Vec<u8>, BlorbError> {
+        let mut source = self
+            .source
+            .as_ref()
+            .ok_or(BlorbError::EndOfFile)?
+            .borrow_mut();
+        source
+            .seek(SeekFrom::Start(offset as u64))
+            .map_err(read_error)?;
+
+        let mut bytes = vec![0u8; 8];
+        source.read_exact(&mut bytes).map_err(read_error)?;
+        let size = bytes_to_usize(&bytes[4..8]);
+        (&mut *source)
+            .take(size as u64)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+
+        if bytes.len() != 8 + size {
+            return Err(BlorbError::TruncatedChunk);
+        }
+        Ok(bytes)
+    }
+
+    /// Read the chunk that starts at the given offset, loading it from the source if needed
+    pub fn read_chunk_at(&self, offset: usize) -> Result<RawBlorbChunk<'_>, BlorbError> {
+        if let (None, Some(chunk)) = (self.bytes.get(), self.chunks.get(&offset)) {
+            if chunk.get().is_none() {
+                let _ = chunk.set(self.load_chunk(offset)?);
+            }
+            if let Some(bytes) = chunk.get() {
+                return chunk_at(bytes, 0, offset);
+            }
+        }
+
+        chunk_at(self.data(), offset, 0)
+    }
+
+    pub fn get_next_chunk(&self, size: usize) -> Result<&[u8], BlorbError> {
+        let offset = *self.cursor.borrow();
+        let bytes = self.get_range(offset, size)?;
+        *self.cursor.borrow_mut() += size + (size % 2);
+        Ok(bytes)
     }
 
     pub fn seek(&self, offset: usize) {
@@ -50,10 +131,14 @@ impl BlorbStream {
         *self.cursor.borrow_mut() = offset;
     }
 
-    pub fn _get_offset(&self) -> usize {
+    pub fn get_offset(&self) -> usize {
         *self.cursor.borrow()
     }
 
+    pub fn get_byte(&self, offset: usize) -> Option<u8> {
+        self.data().get(offset).copied()
+    }
+
     pub fn next_chunk_is(&self, blorb_type: BlorbType) -> bool {
         if let Ok(read_type) = self.read_chunk_type() {
             blorb_type == read_type
@@ -65,36 +150,79 @@ impl BlorbStream {
     pub fn read_chunk_type(&self) -> Result<BlorbType, BlorbError> {
         let offset = *self.cursor.borrow();
 
-        if offset + 4 >= self.bytes.len() {
+        if offset + 4 >= self.data().len() {
             return Err(BlorbError::EndOfFile);
         }
 
         *self.cursor.borrow_mut() += 4;
 
-        (&self.bytes[offset..offset + 4]).try_into()
+        self.get_range(offset, 4)?.try_into()
     }
 
     pub fn read_resource_type(&self) -> Result<ResourceType, BlorbError> {
         let offset = *self.cursor.borrow();
-
-        // TODO: check offset in range
+        let bytes = self.get_range(offset, 4)?;
         *self.cursor.borrow_mut() += 4;
 
-        (&self.bytes[offset..offset + 4]).try_into()
+        bytes.try_into()
     }
 
     pub fn read_chunk_size(&self) -> Result<usize, BlorbError> {
         let offset = *self.cursor.borrow();
-
-        // TODO: check offset in range
+        let bytes = self.get_range(offset, 4)?;
         *self.cursor.borrow_mut() += 4;
-        Ok((self.bytes[offset] as usize) << 24
-            | (self.bytes[offset + 1] as usize) << 16
-            | (self.bytes[offset + 2] as usize) << 8
-            | (self.bytes[offset + 3]) as usize)
+        Ok(bytes_to_usize(bytes))
     }
 }
 
+pub(crate) fn read_error(e: std::io::Error) -> BlorbError {
+    if e.kind() == std::io::ErrorKind::UnexpectedEof {
+        BlorbError::TruncatedChunk
+    } else {
+        BlorbError::ReadError(e.to_string())
+    }
+}
+
+// returns bytes[offset..offset+size], or an error if that extends past the end
+fn get_range(bytes: &[u8], offset: usize, size: usize) -> Result<&[u8], BlorbError> {
+    offset
+        .checked_add(size)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or(BlorbError::TruncatedChunk)
+}
+
+fn bytes_to_usize(bytes: &[u8]) -> usize {
+    (bytes[0] as usize) << 24
+        | (bytes[1] as usize) << 16
+        | (bytes[2] as usize) << 8
+        | bytes[3] as usize
+}
+
+// decode the chunk which starts at offset within bytes, where bytes itself starts at
+// file_offset within the blorb file
+fn chunk_at(
+    bytes: &[u8],
+    offset: usize,
+    file_offset: usize,
+) -> Result<RawBlorbChunk<'_>, BlorbError> {
+    let blorb_type: BlorbType = get_range(bytes, offset, 4)?.try_into()?;
+    let size = bytes_to_usize(get_range(bytes, offset + 4, 4)?);
+
+    // "FORM" type chunks are raw storage in the blorb file, so we need
+    // to return the whole thing instead of just the chunk's data
+    let start_pos = if blorb_type == BlorbType::Form {
+        offset
+    } else {
+        offset + 8
+    };
+
+    Ok(RawBlorbChunk::new(
+        blorb_type,
+        file_offset + offset,
+        get_range(bytes, start_pos, offset + 8 - start_pos + size)?,
+    ))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -113,6 +241,27 @@ mod test {
         assert_eq!(*stream.cursor.borrow(), 4);
     }
 
+    #[test]
+    fn reading_past_the_end_is_an_error() {
+        let stream = BlorbStream::new(vec![0, 0, 0]);
+        assert_eq!(Err(BlorbError::TruncatedChunk), stream.read_chunk_size());
+        assert!(stream.get_next_chunk(4).is_err());
+        assert_eq!(*stream.cursor.borrow(), 0);
+    }
+
+    #[test]
+    fn truncated_chunk_data_is_an_error() {
+        let stream = BlorbStream::new(vec![
+            0x50, 0x4e, 0x47, 0x20, // "PNG "
+            0, 0, 0, 8, // chunk length
+            0x0a, 0x0b, 0x0c, 0x0d, // only half the data
+        ]);
+        assert!(matches!(
+            stream.read_chunk_at(0),
+            Err(BlorbError::TruncatedChunk)
+        ));
+    }
+
     #[test]
     fn non_form_types_just_return_the_data() {
         let stream = BlorbStream::new(vec![
@@ -120,7 +269,7 @@ mod test {
             0, 0, 0, 8, // chunk length
             0x0a, 0x0b, 0x0c, 0x0d, 1, 2, 3, 4, // random data
         ]); // random data
-        let chunk = stream.read_chunk().expect("could not decode chunk");
+        let chunk = stream.read_chunk_at(0).expect("could not decode chunk");
         assert_eq!(BlorbType::Png, chunk.blorb_type);
         assert_eq!(vec![0x0a, 0x0b, 0x0c, 0x0d, 1, 2, 3, 4], chunk.bytes);
     }
@@ -133,8 +282,8 @@ mod test {
             0x49, 0x46, 0x5a, 0x53, // "IFZS"
             1, 2, 3, 4,
         ]); // random data
-        let chunk = stream.read_chunk().expect("Could not decode chunk");
+        let chunk = stream.read_chunk_at(0).expect("Could not decode chunk");
         assert_eq!(BlorbType::Form, chunk.blorb_type);
-        assert_eq!(stream.bytes, chunk.bytes);
+        assert_eq!(stream.data(), chunk.bytes);
     }
 }
@@ -0,0 +1,131 @@
+use crate::types::ResourceType;
+
+/// Assembles blorb files for tests, so fixtures don't have to be written out byte by byte.
+/// Nothing is validated, which makes it possible to build deliberately malformed files
+#[derive(Default)]
+pub struct BlorbBuilder {
+    index: Vec<(ResourceType, u32, usize)>,
+    chunks: Vec<([u8; 4], Vec<u8>)>,
+    form_length: Option<u32>,
+    padding: u8,
+    unpadded_end: bool,
+}
+
+impl BlorbBuilder {
+    /// start an empty blorb file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a chunk along with an entry for it in the resource index
+    pub fn resource(
+        mut self,
+        usage: ResourceType,
+        id: u32,
+        type_code: &[u8; 4],
+        bytes: &[u8],
+    ) -> Self {
+        self.index.push((usage, id, self.chunks.len()));
+        self.chunk(type_code, bytes)
+    }
+
+    /// add a chunk which is not in the resource index
+    pub fn chunk(mut self, type_code: &[u8; 4], bytes: &[u8]) -> Self {
+        self.chunks.push((*type_code, bytes.to_vec()));
+        self
+    }
+
+    /// override the length stored in the FORM header
+    pub fn form_length(mut self, length: u32) -> Self {
+        self.form_length = Some(length);
+        self
+    }
+
+    /// pad odd-length chunks with the given byte instead of zero
+    pub fn padding(mut self, pad: u8) -> Self {
+        self.padding = pad;
+        self
+    }
+
+    /// leave out the padding byte after the last chunk, which some blorb writers do
+    pub fn unpadded_end(mut self) -> Self {
+        self.unpadded_end = true;
+        self
+    }
+
+    /// the bytes of the blorb file
+    pub fn build(&self) -> Vec<u8> {
+        let index_len = 4 + 12 * self.index.len();
+
+        let mut offsets = Vec::new();
+        let mut offset = 20 + index_len;
+        for (_, bytes) in &self.chunks {
+            offsets.push(offset);
+            offset += 8 + bytes.len() + bytes.len() % 2;
+        }
+
+        let mut result = b"FORM\0\0\0\0IFRSRIdx".to_vec();
+        result.extend_from_slice(&(index_len as u32).to_be_bytes());
+        result.extend_from_slice(&(self.index.len() as u32).to_be_bytes());
+        for (usage, id, chunk) in &self.index {
+            result.extend_from_slice(&usage.type_code());
+            result.extend_from_slice(&id.to_be_bytes());
+            result.extend_from_slice(&(offsets[*chunk] as u32).to_be_bytes());
+        }
+
+        for (n, (type_code, bytes)) in self.chunks.iter().enumerate() {
+            result.extend_from_slice(type_code);
+            result.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            result.extend_from_slice(bytes);
+            let is_last = n + 1 == self.chunks.len();
+            if bytes.len() % 2 == 1 && !(is_last && self.unpadded_end) {
+                result.push(self.padding);
+            }
+        }
+
+        let form_length = self.form_length.unwrap_or(result.len() as u32 - 8);
+        result[4..8].copy_from_slice(&form_length.to_be_bytes());
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{types::BlorbType, BlorbReader};
+
+    #[test]
+    fn can_build_a_blorb_with_two_resources() {
+        let bytes = BlorbBuilder::new()
+            .resource(ResourceType::Pict, 1, b"PNG ", &[1, 2, 3])
+            .chunk(b"AUTH", b"someone")
+            .resource(ResourceType::Data, 4, b"TEXT", b"abcd")
+            .build();
+
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+        assert_eq!(2, blorb.resource_count());
+        assert_eq!(Ok(()), blorb.validate_size());
+
+        let pict = blorb
+            .get_resource(ResourceType::Pict, 1)
+            .expect("missing pict");
+        assert_eq!(BlorbType::Png, pict.blorb_type);
+        assert_eq!(&[1, 2, 3], pict.bytes);
+
+        let data = blorb
+            .get_resource(ResourceType::Data, 4)
+            .expect("missing data");
+        assert_eq!(BlorbType::Text, data.blorb_type);
+        assert_eq!(b"abcd", data.bytes);
+    }
+
+    #[test]
+    fn can_build_a_blorb_with_the_wrong_length() {
+        let bytes = BlorbBuilder::new()
+            .chunk(b"AUTH", b"ab")
+            .form_length(100)
+            .build();
+        let blorb = BlorbReader::new(bytes).expect("could not read blorb");
+        assert!(blorb.validate_size().is_err());
+    }
+}
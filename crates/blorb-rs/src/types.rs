@@ -46,6 +46,10 @@ pub enum BlorbType {
     Reso,
     /// An adaptive palette chunk
     Apal,
+    /// A color palette chunk
+    Plte,
+    /// A debugging information chunk, for use by development tools
+    Dbug,
 
     // Images
     /// A PNG image chunk
@@ -64,6 +68,53 @@ pub enum BlorbType {
     Song,
 }
 
+/// The broad kind of content held in a chunk of a given BlorbType
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum BlorbCategory {
+    /// A picture
+    Image,
+    /// A sound
+    Sound,
+    /// A game executable
+    Executable,
+    /// Information about the blorb file or the game, including the chunks which give the
+    /// file its structure
+    Metadata,
+    /// A text data resource
+    Text,
+    /// A binary data resource. A FORM chunk may be a sound rather than data, and is
+    /// counted here
+    Data,
+}
+
+impl BlorbType {
+    /// Returns the broad kind of content held in a chunk of this type
+    pub fn category(&self) -> BlorbCategory {
+        match self {
+            Self::Png | Self::Jpeg | Self::Rect => BlorbCategory::Image,
+            Self::Mod | Self::Oggv | Self::Song => BlorbCategory::Sound,
+            Self::Zcod | Self::Glul => BlorbCategory::Executable,
+            Self::Form | Self::Bina => BlorbCategory::Data,
+            Self::Text => BlorbCategory::Text,
+            Self::Ifrs
+            | Self::Ridx
+            | Self::Ifmd
+            | Self::Fspc
+            | Self::Rdes
+            | Self::Auth
+            | Self::Copr
+            | Self::Anno
+            | Self::Ifhd
+            | Self::Snam
+            | Self::Reln
+            | Self::Reso
+            | Self::Apal
+            | Self::Plte
+            | Self::Dbug => BlorbCategory::Metadata,
+        }
+    }
+}
+
 /// In the RIdx chunk, the file defines four different types of resources
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum ResourceType {
@@ -79,6 +130,16 @@ pub enum ResourceType {
 
 macro_rules! blorb_type_try_from {
     ($type:ident, $($blorbType:ident => $string:expr),*) => {
+        impl $type {
+            /// The four-character code which identifies this type in a blorb file
+            pub fn type_code(&self) -> [u8; 4] {
+                let code = match self {
+                    $(Self::$blorbType => $string.as_bytes(),)*
+                };
+                [code[0], code[1], code[2], code[3]]
+            }
+        }
+
         impl TryFrom<String> for $type {
             type Error = BlorbError;
 
@@ -139,6 +200,8 @@ blorb_type_try_from!(
     Reln => "RelN",
     Reso => "Reso",
     Apal => "APal",
+    Plte => "Plte",
+    Dbug => "Dbug",
     Text => "TEXT",
     Bina => "BINA",
     Ifhd => "IFhd",
@@ -184,8 +247,24 @@ mod test {
         assert_eq!(Ok(ResourceType::Pict), "Pict".to_string().try_into());
     }
 
+    #[test]
+    fn can_categorize_blorb_types() {
+        assert_eq!(BlorbCategory::Image, BlorbType::Png.category());
+        assert_eq!(BlorbCategory::Sound, BlorbType::Oggv.category());
+        assert_eq!(BlorbCategory::Executable, BlorbType::Zcod.category());
+        assert_eq!(BlorbCategory::Metadata, BlorbType::Auth.category());
+        assert_eq!(BlorbCategory::Text, BlorbType::Text.category());
+        assert_eq!(BlorbCategory::Data, BlorbType::Bina.category());
+    }
+
     #[test]
     fn can_convert_rdes() {
         assert_eq!(Ok(BlorbType::Rdes), "RDes".to_string().try_into());
     }
+
+    #[test]
+    fn can_convert_dbug() {
+        assert_eq!(Ok(BlorbType::Dbug), "Dbug".to_string().try_into());
+        assert_eq!(*b"Dbug", BlorbType::Dbug.type_code());
+    }
 }
@@ -1,4 +1,4 @@
-use blorb::{chunk::BlorbChunk, error::BlorbError, BlorbReader};
+use blorb::{chunk::BlorbChunk, BlorbReader};
 
 fn main() {
     let filename = std::env::args().nth(1).unwrap();
@@ -11,7 +11,7 @@ fn main() {
             match chunk {
                 Ok(chunk) => match TryInto::<BlorbChunk>::try_into(&chunk) {
                     Ok(chunk) => println!("{chunk:?}"),
-                    Err(e) if e == BlorbError::ConversionFailed => println!("{chunk:?}"),
+                    Err(e) if e.is_conversion_failure() => println!("{chunk:?}"),
                     Err(e) => panic!("interpration failed - {e}"),
                 },
                 Err(e) => panic!("invalid chunk - {e}"),
@@ -40,7 +40,7 @@ fn main() {
         let buf = [0u32; 80];
         glk.request_line_event_uni(win, &buf, 0);
         match glk.select() {
-            GlkEvent::LineInput { win, buf } => {
+            GlkEvent::LineInput { win, buf, .. } => {
                 println!("window {win} sent line input event: {buf:?}")
             }
             x => panic!("got {x:?} instead of a line input"),
@@ -40,6 +40,15 @@ impl GlkWindow for SimpleWindow {
                     println!("[SPLIT parent {parent} -> {winid}]");
                 }
                 GlkMessage::Close(winid) => println!("[CLOSE window {winid}]"),
+                GlkMessage::SetStyle { winid, style } => {
+                    println!("[STYLE window {winid}: {style:?}]");
+                }
+                GlkMessage::SetHyperlink { winid, linkval } => {
+                    println!("[LINK window {winid}: {linkval}]");
+                }
+                GlkMessage::DrawImage { winid, image, .. } => {
+                    println!("[IMAGE {image} in window {winid}]");
+                }
             }
         }
     }
@@ -56,6 +65,15 @@ impl GlkWindow for SimpleWindow {
         todo!()
     }
 
+    fn get_char(&mut self, win: GlkWindowID, tx: Sender<GlkEvent>) {
+        let _ = thread::spawn(move || {
+            let mut line = String::new();
+            let _ = std::io::stdin().read_line(&mut line);
+            let key = line.chars().next().map_or(Keycode::Return, Keycode::from);
+            let _ = tx.send(GlkEvent::CharInput { win, key });
+        });
+    }
+
     fn get_line(&mut self, event: LineInput, _initlen: usize, tx: Sender<GlkEvent>) {
         let win = self.winid;
         println!("get line from {win}");
@@ -80,16 +98,22 @@ impl GlkWindow for SimpleWindow {
             };
             let mut line = String::new();
             let _ = std::io::stdin().read_line(&mut line); // <- convert to actual readline
+            let line = line.trim_end_matches(['\r', '\n']);
             println!(">>> read '{line}' <<<");
+            let len = line.chars().count();
             let _ = tx.send(if is_latin1 {
                 GlkEvent::LineInput {
                     win,
-                    buf: LineInput::Latin1(line.into()),
+                    buf: LineInput::Latin1(line.chars().map(|ch| ch as u8).collect()),
+                    len,
+                    terminator: None,
                 }
             } else {
                 GlkEvent::LineInput {
                     win,
                     buf: LineInput::Unicode(line.chars().map(|ch| ch as u32).collect::<Vec<_>>()),
+                    len,
+                    terminator: None,
                 }
             });
         });
@@ -1,6 +1,14 @@
+use std::{
+    fs::File,
+    io::Write,
+    time::{Duration, Instant},
+};
+
 use crate::{
-    events::GlkEvent,
-    windows::{GlkWindow, GlkWindowID},
+    events::{CharRequest, GlkEvent, LineInput, RecordedInput, RequestKind},
+    file_stream::GlkFileRef,
+    keycode::Keycode,
+    windows::{GlkWindow, GlkWindowID, GlkWindowType},
 };
 
 use super::Glk;
@@ -11,12 +19,146 @@ impl<T: GlkWindow + Default> Glk<T> {
      */
     /// Block until event arrives
     pub fn select(&mut self) -> GlkEvent {
-        self.event_mgr.block_until_event()
+        self.flush_windows();
+        let mut event = self.event_mgr.block_until_event();
+        self.complete_request(&mut event);
+        event
     }
 
     /// check to see if events are available, and return one. Otherwise return GlkEvent::None
     pub fn select_poll(&mut self) -> GlkEvent {
-        self.event_mgr.pop_event()
+        self.flush_windows();
+        let mut event = self.event_mgr.pop_event();
+        self.complete_request(&mut event);
+        event
+    }
+
+    /// return all the events that arrive within the timeout, or only those that are
+    /// already available if the timeout is None
+    pub fn drain_events(&mut self, timeout: Option<Duration>) -> Vec<GlkEvent> {
+        self.flush_windows();
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut events = Vec::new();
+        loop {
+            let mut event = match deadline {
+                Some(deadline) => self.event_mgr.wait_for_event(deadline),
+                None => self.event_mgr.pop_event(),
+            };
+            if event == GlkEvent::None {
+                return events;
+            }
+            self.complete_request(&mut event);
+            events.push(event);
+        }
+    }
+
+    // An input event means that the window's input request has been satisfied
+    fn complete_request(&mut self, event: &mut GlkEvent) {
+        self.record_input(event);
+
+        match event {
+            GlkEvent::LineInput { win, .. } => {
+                if let Some(winref) = self.win_mgr.get_ref(*win) {
+                    winref.set_line_request(false);
+                }
+            }
+            GlkEvent::CharInput { win, key } => {
+                let Some(winref) = self.win_mgr.get_ref(*win) else {
+                    return;
+                };
+                // a Latin-1 request can't return characters outside of Latin-1
+                if let Keycode::Basic(ch) = key {
+                    if winref.get_char_request() == Some(CharRequest::Latin1) && *ch as u32 > 0xff {
+                        *key = Keycode::Unknown;
+                    }
+                }
+                winref.set_char_request(None);
+            }
+            GlkEvent::Mouse { win, .. } => {
+                if let Some(winref) = self.win_mgr.get_ref(*win) {
+                    winref.set_mouse_request(false);
+                }
+            }
+            GlkEvent::Hyperlink { win, .. } => {
+                if let Some(winref) = self.win_mgr.get_ref(*win) {
+                    winref.set_hyperlink_request(false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Add an event to the end of the event queue, as if it had come from the window system.
+    /// Useful for testing event-driven code without a full window back end.
+    pub fn post_event(&mut self, event: GlkEvent) {
+        self.event_mgr.post_event(event);
+    }
+
+    /// list every outstanding input request, in window order
+    pub fn pending_requests(&self) -> Vec<(GlkWindowID, RequestKind)> {
+        let mut wins = self.win_mgr.get_iter().collect::<Vec<_>>();
+        wins.sort();
+
+        let mut result = Vec::new();
+        for win in wins {
+            let Some(winref) = self.win_mgr.get_ref(win) else {
+                continue;
+            };
+            if winref.has_line_request() {
+                result.push((win, RequestKind::Line));
+            }
+            if winref.get_char_request().is_some() {
+                result.push((win, RequestKind::Char));
+            }
+            if winref.has_mouse_request() {
+                result.push((win, RequestKind::Mouse));
+            }
+            if winref.has_hyperlink_request() {
+                result.push((win, RequestKind::Hyperlink));
+            }
+        }
+        result
+    }
+
+    /*
+     * Glk Section 4.1 - Character Input Events
+     */
+
+    /// Request a single Latin-1 keystroke from a given window
+    pub fn request_char_event(&mut self, win: GlkWindowID) {
+        self.queue_char_request(win, CharRequest::Latin1);
+    }
+
+    /// Request a single unicode keystroke from a given window
+    pub fn request_char_event_uni(&mut self, win: GlkWindowID) {
+        self.queue_char_request(win, CharRequest::Unicode);
+    }
+
+    fn queue_char_request(&mut self, win: GlkWindowID, request: CharRequest) {
+        let winref = self
+            .win_mgr
+            .get_ref(win)
+            .expect("char input event requested from non-existent window");
+        self.stream_flush(winref.get_stream());
+        winref.set_char_request(Some(request));
+        if let Some(RecordedInput::Key(key)) = self.replay.front() {
+            let event = GlkEvent::CharInput { win, key: *key };
+            self.replay.pop_front();
+            self.event_mgr.post_event(event);
+            return;
+        }
+        self.event_mgr.queue_char_input_request(&winref);
+    }
+
+    /// Cancel a pending character input request
+    pub fn cancel_char_event(&mut self, win: GlkWindowID) {
+        let Some(winref) = self.win_mgr.get_ref(win) else {
+            return;
+        };
+        if winref.get_char_request().is_some() {
+            winref.cancel_char();
+            winref.set_char_request(None);
+        }
     }
 
     /*
@@ -29,8 +171,25 @@ impl<T: GlkWindow + Default> Glk<T> {
             .win_mgr
             .get_ref(win)
             .expect("line input event requested from non-existent window");
+        self.stream_flush(winref.get_stream());
+        winref.set_line_request(true);
+        if let Some(line) = self.replay_line(buf.len()) {
+            let len = line.chars().count();
+            let buf = LineInput::Latin1(
+                line.chars()
+                    .map(|ch| u8::try_from(ch).unwrap_or(b'?'))
+                    .collect(),
+            );
+            self.event_mgr.post_event(GlkEvent::LineInput {
+                win,
+                buf,
+                len,
+                terminator: None,
+            });
+            return;
+        }
         self.event_mgr
-            .queue_line_input_request(&winref, buf, initlen);
+            .queue_line_input_request(&winref, buf, initlen, &winref.get_line_settings());
     }
 
     /// request a line of unicode codepoint from a given window
@@ -39,8 +198,83 @@ impl<T: GlkWindow + Default> Glk<T> {
             .win_mgr
             .get_ref(win)
             .expect("line input event requested from non-existent window");
-        self.event_mgr
-            .queue_line_input_uni_request(&winref, buf, initlen);
+        self.stream_flush(winref.get_stream());
+        winref.set_line_request(true);
+        if let Some(line) = self.replay_line(buf.len()) {
+            let len = line.chars().count();
+            let buf = LineInput::Unicode(line.chars().map(|ch| ch as u32).collect());
+            self.event_mgr.post_event(GlkEvent::LineInput {
+                win,
+                buf,
+                len,
+                terminator: None,
+            });
+            return;
+        }
+        self.event_mgr.queue_line_input_uni_request(
+            &winref,
+            buf,
+            initlen,
+            &winref.get_line_settings(),
+        );
+    }
+
+    /// Choose whether lines entered in a window are shown in it afterwards. This takes
+    /// effect from the next line input request
+    pub fn set_echo_line_event(&mut self, win: GlkWindowID, echo: bool) {
+        if let Some(winref) = self.win_mgr.get_ref(win) {
+            winref.set_line_echo(echo);
+        }
+    }
+
+    /// Choose which keys end line input in a window as well as Return. Keys which can't
+    /// end a line are left out. This takes effect from the next line input request
+    pub fn set_terminators_line_event(&mut self, win: GlkWindowID, keys: Vec<Keycode>) {
+        if let Some(winref) = self.win_mgr.get_ref(win) {
+            winref.set_line_terminators(
+                keys.into_iter()
+                    .filter(|key| key.is_line_terminator())
+                    .collect(),
+            );
+        }
+    }
+
+    /// returns how many characters have been typed so far into the window's pending line
+    /// input request, or None if there is no pending request
+    pub fn line_input_length(&self, win: GlkWindowID) -> Option<usize> {
+        let winref = self.win_mgr.get_ref(win)?;
+        if !winref.has_line_request() {
+            return None;
+        }
+        Some(winref.get_line_length())
+    }
+
+    /*
+     * Glk Section 4.3 - Mouse Input Events
+     */
+
+    /// Request a mouse click from a text grid or graphics window. Other window types do
+    /// not accept mouse input, and ignore the request
+    pub fn request_mouse_event(&mut self, win: GlkWindowID) {
+        let Some(winref) = self.win_mgr.get_ref(win) else {
+            return;
+        };
+        if matches!(
+            winref.get_type(),
+            GlkWindowType::TextGrid | GlkWindowType::Graphics
+        ) && !winref.has_mouse_request()
+        {
+            self.event_mgr.queue_mouse_request(&winref);
+        }
+    }
+
+    /// Cancel a pending mouse input request
+    pub fn cancel_mouse_event(&mut self, win: GlkWindowID) {
+        if let Some(winref) = self.win_mgr.get_ref(win) {
+            if winref.has_mouse_request() {
+                winref.cancel_mouse();
+            }
+        }
     }
 
     /*
@@ -51,11 +285,91 @@ impl<T: GlkWindow + Default> Glk<T> {
     pub fn request_timer_events(&mut self, millisecs: u32) {
         self.event_mgr.set_timer(millisecs)
     }
+
+    /*
+     * Recording and Replaying Input
+     */
+
+    /// Record every line and keystroke delivered to the game from now on in a file, one
+    /// per line, or stop recording if fileref is None. Returns false if the file could not
+    /// be created
+    pub fn set_input_log(&mut self, fileref: Option<GlkFileRef>) -> bool {
+        self.input_log = None;
+        let Some(fileref) = fileref else {
+            return true;
+        };
+        let Some(fileref) = self.fileref_mgr.get(fileref) else {
+            return false;
+        };
+        self.input_log = File::create(&fileref.name).ok();
+        self.input_log.is_some()
+    }
+
+    /// Answer input requests with the input recorded in a file, instead of asking the
+    /// window back end, until the recording runs out. Returns false if the file could not
+    /// be read
+    pub fn replay_input(&mut self, fileref: GlkFileRef) -> bool {
+        let Some(fileref) = self.fileref_mgr.get(fileref) else {
+            return false;
+        };
+        let Ok(records) = std::fs::read_to_string(&fileref.name) else {
+            return false;
+        };
+        self.replay = records
+            .lines()
+            .filter_map(RecordedInput::from_record)
+            .collect();
+        true
+    }
+
+    fn record_input(&mut self, event: &GlkEvent) {
+        let (Some(log), Some(input)) = (self.input_log.as_mut(), RecordedInput::from_event(event))
+        else {
+            return;
+        };
+        let _ = writeln!(log, "{}", input.to_record());
+    }
+
+    // the next recorded line, cut down to fit the buffer it is read into
+    fn replay_line(&mut self, maxlen: usize) -> Option<String> {
+        let Some(RecordedInput::Line(line)) = self.replay.front() else {
+            return None;
+        };
+        let line = line.chars().take(maxlen).collect();
+        self.replay.pop_front();
+        Some(line)
+    }
+
+    /*
+     * Glk Section 9.2 - Accepting Hyperlink Events
+     */
+
+    /// Request that the next hyperlink selected in a window be reported as an event
+    pub fn request_hyperlink_event(&mut self, win: GlkWindowID) {
+        let Some(winref) = self.win_mgr.get_ref(win) else {
+            return;
+        };
+        if !winref.has_hyperlink_request() {
+            self.event_mgr.queue_hyperlink_request(&winref);
+        }
+    }
+
+    /// Cancel a pending hyperlink request
+    pub fn cancel_hyperlink_event(&mut self, win: GlkWindowID) {
+        if let Some(winref) = self.win_mgr.get_ref(win) {
+            if winref.has_hyperlink_request() {
+                winref.cancel_hyperlink();
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::windows::testwin::GlkTestWindow;
+    use crate::windows::{
+        testwin::GlkTestWindow, WindowSplitAmount, WindowSplitMethod, WindowSplitPosition,
+    };
+    use crate::GlkFileUsage;
 
     use super::*;
 
@@ -65,4 +379,426 @@ mod test {
             assert_eq!(glk.select_poll(), GlkEvent::None);
         });
     }
+
+    #[test]
+    fn can_list_pending_requests() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let win2 = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextGrid,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Above,
+                        amount: WindowSplitAmount::Fixed(3),
+                        border: false,
+                    }),
+                    84,
+                )
+                .unwrap();
+            assert!(glk.pending_requests().is_empty());
+
+            glk.request_line_event(win1, &[0; 10], 0);
+            glk.request_char_event(win2);
+            assert_eq!(
+                glk.pending_requests(),
+                vec![(win1, RequestKind::Line), (win2, RequestKind::Char)]
+            );
+
+            glk.cancel_char_event(win2);
+            assert_eq!(glk.pending_requests(), vec![(win1, RequestKind::Line)]);
+        });
+    }
+
+    #[test]
+    fn can_post_an_event() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            glk.post_event(GlkEvent::Mouse { win: 1, x: 3, y: 4 });
+            assert_eq!(glk.select(), GlkEvent::Mouse { win: 1, x: 3, y: 4 });
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+        });
+    }
+
+    #[test]
+    fn can_drain_all_pending_events() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            glk.post_event(GlkEvent::Mouse { win: 1, x: 3, y: 4 });
+            glk.post_event(GlkEvent::Arrange { win: 1 });
+            glk.post_event(GlkEvent::Redraw { win: 1 });
+            assert_eq!(
+                glk.drain_events(None),
+                vec![
+                    GlkEvent::Mouse { win: 1, x: 3, y: 4 },
+                    GlkEvent::Arrange { win: 1 },
+                    GlkEvent::Redraw { win: 1 },
+                ]
+            );
+            assert!(glk.drain_events(Some(Duration::from_millis(10))).is_empty());
+        });
+    }
+
+    #[test]
+    fn can_read_a_keystroke() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            glk.t_get_winref(win)
+                .winref
+                .borrow()
+                .backend()
+                .borrow_mut()
+                .set_input_buffer("yé");
+
+            glk.request_char_event(win);
+            assert_eq!(
+                glk.select(),
+                GlkEvent::CharInput {
+                    win,
+                    key: Keycode::Basic('y')
+                }
+            );
+
+            glk.request_char_event_uni(win);
+            assert_eq!(
+                glk.select(),
+                GlkEvent::CharInput {
+                    win,
+                    key: Keycode::Basic('é')
+                }
+            );
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+        });
+    }
+
+    #[test]
+    fn latin1_keystroke_requests_cannot_return_unicode() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            glk.t_get_winref(win)
+                .winref
+                .borrow()
+                .backend()
+                .borrow_mut()
+                .set_input_buffer("→");
+
+            glk.request_char_event(win);
+            assert_eq!(
+                glk.select(),
+                GlkEvent::CharInput {
+                    win,
+                    key: Keycode::Unknown
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn can_cancel_a_keystroke_request() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            glk.request_char_event(win);
+            glk.cancel_char_event(win);
+            assert_eq!(glk.t_get_winref(win).get_char_request(), None);
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+        });
+    }
+
+    #[test]
+    fn mouse_clicks_are_reported_for_grid_and_graphics_windows() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let grid = glk
+                .window_open(None, GlkWindowType::TextGrid, None, 73)
+                .unwrap();
+            let text = glk
+                .window_open(
+                    Some(grid),
+                    GlkWindowType::TextBuffer,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Below,
+                        amount: WindowSplitAmount::Proportional(50),
+                        border: false,
+                    }),
+                    74,
+                )
+                .unwrap();
+            let click = |glk: &Glk<GlkTestWindow>, win, x, y| {
+                glk.t_get_winref(win)
+                    .winref
+                    .borrow()
+                    .backend()
+                    .borrow_mut()
+                    .click(x, y)
+            };
+
+            // text buffers don't take mouse input
+            glk.request_mouse_event(text);
+            click(glk, text, 1, 1);
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+
+            glk.request_mouse_event(grid);
+            click(glk, grid, 3, 4);
+            assert_eq!(
+                glk.select(),
+                GlkEvent::Mouse {
+                    win: grid,
+                    x: 3,
+                    y: 4
+                }
+            );
+
+            // each request is good for one click
+            click(glk, grid, 5, 6);
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+
+            glk.request_mouse_event(grid);
+            glk.cancel_mouse_event(grid);
+            click(glk, grid, 7, 8);
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+            assert!(!glk.t_get_winref(grid).has_mouse_request());
+        });
+    }
+
+    #[test]
+    fn selected_hyperlinks_are_reported() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let follow_link = |glk: &Glk<GlkTestWindow>, linkval| {
+                glk.t_get_winref(win)
+                    .winref
+                    .borrow()
+                    .backend()
+                    .borrow_mut()
+                    .follow_link(linkval)
+            };
+
+            glk.request_hyperlink_event(win);
+            follow_link(glk, 7);
+            assert_eq!(glk.select(), GlkEvent::Hyperlink { win, linkval: 7 });
+            assert!(!glk.t_get_winref(win).has_hyperlink_request());
+
+            glk.request_hyperlink_event(win);
+            glk.cancel_hyperlink_event(win);
+            follow_link(glk, 8);
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+        });
+    }
+
+    #[test]
+    fn a_configured_terminator_ends_the_line() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            glk.set_echo_line_event(win, false);
+            glk.set_terminators_line_event(
+                win,
+                vec![Keycode::Escape, Keycode::Basic('x'), Keycode::Func1],
+            );
+            glk.request_line_event(win, &[0u8; 80], 0);
+
+            let winref = glk.t_get_winref(win);
+            let window = winref.winref.borrow();
+            let mut backend = window.backend().borrow_mut();
+            assert!(!backend.line_settings.echo);
+            assert_eq!(
+                backend.line_settings.terminators,
+                vec![Keycode::Escape, Keycode::Func1]
+            );
+
+            assert!(!backend.end_line(win, "hel", Keycode::Basic('x')));
+            assert!(backend.end_line(win, "help", Keycode::Func1));
+            drop(backend);
+            drop(window);
+
+            assert_eq!(
+                glk.select(),
+                GlkEvent::LineInput {
+                    win,
+                    buf: LineInput::Latin1(b"help".to_vec()),
+                    len: 4,
+                    terminator: Some(Keycode::Func1),
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn ended_lines_are_converted_to_latin1() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            glk.request_line_event(win, &[0u8; 80], 0);
+
+            let winref = glk.t_get_winref(win);
+            let window = winref.winref.borrow();
+            let mut backend = window.backend().borrow_mut();
+            assert!(backend.end_line(win, "caf\u{e9} \u{2603}", Keycode::Return));
+            drop(backend);
+            drop(window);
+
+            assert_eq!(
+                glk.select(),
+                GlkEvent::LineInput {
+                    win,
+                    buf: LineInput::Latin1(b"caf\xe9 ?".to_vec()),
+                    len: 6,
+                    terminator: None,
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn can_replay_recorded_input() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let fileref = glk
+                .fileref_create_temp(GlkFileUsage::InputRecord, 74)
+                .unwrap();
+
+            assert!(glk.set_input_log(Some(fileref)));
+            let mut recorded = Vec::new();
+            for line in ["open mailbox", "read leaflet"] {
+                glk.request_line_event(win, &[0u8; 80], 0);
+                glk.post_event(GlkEvent::LineInput {
+                    win,
+                    buf: LineInput::Latin1(line.as_bytes().to_vec()),
+                    len: line.len(),
+                    terminator: None,
+                });
+                recorded.push(glk.select());
+            }
+            glk.request_char_event(win);
+            glk.post_event(GlkEvent::CharInput {
+                win,
+                key: Keycode::Func1,
+            });
+            recorded.push(glk.select());
+            assert!(glk.set_input_log(None));
+
+            assert!(glk.replay_input(fileref));
+            glk.request_line_event(win, &[0u8; 80], 0);
+            glk.request_line_event(win, &[0u8; 80], 0);
+            glk.request_char_event(win);
+            assert_eq!(glk.drain_events(None), recorded);
+
+            // once the recording runs out, input comes from the back end again
+            glk.request_line_event(win, &[0u8; 80], 0);
+            assert_eq!(glk.select_poll(), GlkEvent::None);
+        });
+    }
+
+    #[test]
+    fn replayed_latin1_lines_replace_other_characters() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let fileref = glk
+                .fileref_create_temp(GlkFileUsage::InputRecord, 74)
+                .unwrap();
+
+            assert!(glk.set_input_log(Some(fileref)));
+            let line = "caf\u{e9} \u{2603}";
+            glk.request_line_event_uni(win, &[0u32; 80], 0);
+            glk.post_event(GlkEvent::LineInput {
+                win,
+                buf: LineInput::Unicode(line.chars().map(|ch| ch as u32).collect()),
+                len: 6,
+                terminator: None,
+            });
+            glk.select();
+            assert!(glk.set_input_log(None));
+
+            assert!(glk.replay_input(fileref));
+            glk.request_line_event(win, &[0u8; 80], 0);
+            assert_eq!(
+                glk.select(),
+                GlkEvent::LineInput {
+                    win,
+                    buf: LineInput::Latin1(b"caf\xe9 ?".to_vec()),
+                    len: 6,
+                    terminator: None,
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn can_count_characters_in_pending_line_input() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            assert_eq!(glk.line_input_length(win), None);
+
+            glk.request_line_event(win, &[0u8; 80], 0);
+            assert_eq!(glk.line_input_length(win), Some(0));
+
+            let winref = glk.t_get_winref(win);
+            winref
+                .winref
+                .borrow()
+                .backend()
+                .borrow_mut()
+                .set_input_buffer("look");
+            *winref
+                .winref
+                .borrow()
+                .backend()
+                .borrow()
+                .input_cursor
+                .borrow_mut() = 3;
+            assert_eq!(glk.line_input_length(win), Some(3));
+
+            glk.post_event(GlkEvent::LineInput {
+                win,
+                buf: LineInput::Latin1(b"look".to_vec()),
+                len: 4,
+                terminator: None,
+            });
+            glk.select();
+            assert_eq!(glk.line_input_length(win), None);
+        });
+    }
+
+    #[test]
+    fn line_input_reports_its_terminator() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            glk.request_line_event(win, &[0u8; 80], 0);
+
+            // the back end ended the line because a function key was pressed
+            glk.post_event(GlkEvent::LineInput {
+                win,
+                buf: LineInput::Latin1(b"help".to_vec()),
+                len: 4,
+                terminator: Some(Keycode::Func1),
+            });
+            assert_eq!(
+                glk.select(),
+                GlkEvent::LineInput {
+                    win,
+                    buf: LineInput::Latin1(b"help".to_vec()),
+                    len: 4,
+                    terminator: Some(Keycode::Func1),
+                }
+            );
+            assert_eq!(glk.line_input_length(win), None);
+        });
+    }
 }
@@ -0,0 +1,58 @@
+use crate::{prelude::GlkRock, schannel::GlkSChannelID, windows::GlkWindow};
+
+use super::Glk;
+
+impl<T: GlkWindow + Default> Glk<T> {
+    /*
+     * Glk Section 8.2 - Creating and Destroying Sound Channels
+     */
+
+    /// create a new sound channel
+    pub fn schannel_create(&mut self, rock: GlkRock) -> Option<GlkSChannelID> {
+        Some(self.schannel_mgr.create(rock))
+    }
+
+    /// destroy a sound channel
+    pub fn schannel_destroy(&mut self, chan: GlkSChannelID) {
+        self.schannel_mgr.destroy(chan);
+    }
+
+    /*
+     * Glk Section 8.5 - Other Sound Channel Functions
+     */
+
+    /// iterate over all of the sound channels
+    pub fn schannel_iterate(&self) -> std::vec::IntoIter<GlkSChannelID> {
+        self.schannel_mgr.get_iter()
+    }
+
+    /// get the rock value for a given sound channel
+    pub fn schannel_get_rock(&self, chan: GlkSChannelID) -> Option<GlkRock> {
+        self.schannel_mgr.get_rock(chan)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::windows::testwin::GlkTestWindow;
+
+    use super::*;
+
+    #[test]
+    fn can_iterate_sound_channels() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let chan1 = glk.schannel_create(11).unwrap();
+            let chan2 = glk.schannel_create(22).unwrap();
+            let chan3 = glk.schannel_create(33).unwrap();
+            glk.schannel_destroy(chan2);
+
+            assert_eq!(
+                glk.schannel_iterate().collect::<Vec<_>>(),
+                vec![chan1, chan3]
+            );
+            assert_eq!(glk.schannel_get_rock(chan1), Some(11));
+            assert_eq!(glk.schannel_get_rock(chan3), Some(33));
+            assert_eq!(glk.schannel_get_rock(chan2), None);
+        });
+    }
+}
@@ -1,11 +1,15 @@
 use std::{cell::RefCell, rc::Rc};
 
+use blorb::BlorbReader;
+
 use crate::{
     file_stream::{FileStream, GlkFileRef},
-    mem_stream::MemStream,
+    mem_stream::{MemStream, MemStreamUni},
     prelude::GlkRock,
-    stream::{GlkStreamID, GlkStreamResult},
-    windows::GlkWindow,
+    resource_stream::ResourceStream,
+    stream::{GlkStreamID, GlkStreamResult, StreamCloseData},
+    style::{GlkStyle, StyleHint},
+    windows::{GlkWindow, GlkWindowType},
     Glk, GlkFileMode, GlkSeekMode,
 };
 
@@ -68,8 +72,41 @@ impl<T: GlkWindow + Default> Glk<T> {
         }
     }
 
+    /// format a value and write it to the default stream
+    pub fn print(&mut self, value: impl std::fmt::Display) {
+        self.put_string(&value.to_string());
+    }
+
+    /// format a value and write it, followed by a newline, to the default stream
+    pub fn println(&mut self, value: impl std::fmt::Display) {
+        self.put_string(&format!("{value}\n"));
+    }
+
+    /// Install a filter which every character goes through on its way to a window. The
+    /// filter returns the text to show in place of the character, or None to drop it
+    pub fn set_output_filter(&mut self, filter: Box<dyn Fn(char) -> Option<String>>) {
+        self.output_filter = Some(filter);
+    }
+
+    // returns None if the text can be written as it is
+    fn filter_output(
+        &mut self,
+        streamid: GlkStreamID,
+        text: impl Iterator<Item = char>,
+    ) -> Option<String> {
+        if !self.stream_mgr.get(streamid)?.is_window_stream() {
+            return None;
+        }
+        let filter = self.output_filter.as_ref()?;
+        Some(text.filter_map(filter).collect())
+    }
+
     /// write a byte to a stream
     pub fn put_char_stream(&mut self, streamid: GlkStreamID, ch: u8) {
+        if let Some(filtered) = self.filter_output(streamid, std::iter::once(ch as char)) {
+            return self.write_string(streamid, &filtered);
+        }
+
         let Some(stream) = self.stream_mgr.get(streamid) else {
             return;
         };
@@ -86,6 +123,11 @@ impl<T: GlkWindow + Default> Glk<T> {
 
     /// write a unicode string to a stream
     pub fn put_string_stream(&mut self, streamid: GlkStreamID, s: &str) {
+        let filtered = self.filter_output(streamid, s.chars());
+        self.write_string(streamid, filtered.as_deref().unwrap_or(s));
+    }
+
+    fn write_string(&mut self, streamid: GlkStreamID, s: &str) {
         let Some(stream) = self.stream_mgr.get(streamid) else {
             return;
         };
@@ -96,7 +138,7 @@ impl<T: GlkWindow + Default> Glk<T> {
         }
 
         if let Some(echo) = stream.get_echo_stream() {
-            self.put_string_stream(echo, s);
+            self.write_string(echo, s);
         }
     }
 
@@ -108,6 +150,10 @@ impl<T: GlkWindow + Default> Glk<T> {
 
     /// write a buffer of bytes to a stream
     pub fn put_buffer_stream(&mut self, streamid: GlkStreamID, buf: &[u8]) {
+        if let Some(filtered) = self.filter_output(streamid, buf.iter().map(|b| *b as char)) {
+            return self.write_string(streamid, &filtered);
+        }
+
         let Some(stream) = self.stream_mgr.get(streamid) else {
             return;
         };
@@ -124,6 +170,10 @@ impl<T: GlkWindow + Default> Glk<T> {
 
     /// write a unicode character to a stream
     pub fn put_char_stream_uni(&mut self, streamid: GlkStreamID, ch: char) {
+        if let Some(filtered) = self.filter_output(streamid, std::iter::once(ch)) {
+            return self.write_string(streamid, &filtered);
+        }
+
         let Some(stream) = self.stream_mgr.get(streamid) else {
             return;
         };
@@ -140,6 +190,10 @@ impl<T: GlkWindow + Default> Glk<T> {
 
     /// write a buffer of unicode characters to a stream
     pub fn put_buffer_stream_uni(&mut self, streamid: GlkStreamID, buf: &[char]) {
+        if let Some(filtered) = self.filter_output(streamid, buf.iter().copied()) {
+            return self.write_string(streamid, &filtered);
+        }
+
         let Some(stream) = self.stream_mgr.get(streamid) else {
             return;
         };
@@ -154,6 +208,22 @@ impl<T: GlkWindow + Default> Glk<T> {
         }
     }
 
+    /// send any buffered output on a stream to where it is going, and return how many
+    /// characters were written. Only window streams buffer their output
+    pub fn stream_flush(&mut self, streamid: GlkStreamID) -> u32 {
+        let Some(stream) = self.stream_mgr.get(streamid) else {
+            return 0;
+        };
+
+        let wr = stream.flush();
+        let len = if wr.wait_needed {
+            stream.await_response(self.response.as_ref().unwrap())
+        } else {
+            wr.len
+        };
+        u32::try_from(len).unwrap_or(u32::MAX)
+    }
+
     /*
      * Section 5.2 - How to Read
      */
@@ -186,6 +256,47 @@ impl<T: GlkWindow + Default> Glk<T> {
         }
     }
 
+    /// returns how many line breaks have been read from a stream so far
+    pub fn stream_line_count(&mut self, streamid: GlkStreamID) -> u32 {
+        self.stream_mgr
+            .get(streamid)
+            .map_or(0, |stream| stream.get_lines_read())
+    }
+
+    /// read up to count lines from a stream, stopping early at end-of-stream. The newlines
+    /// are consumed, but are not included in the results
+    pub fn get_lines_stream(&mut self, streamid: GlkStreamID, count: usize) -> Vec<Vec<u8>> {
+        let mut lines = Vec::new();
+        while lines.len() < count {
+            // read a byte at a time so that an empty line can be told apart from the end
+            // of the stream
+            let mut line = Vec::new();
+            loop {
+                match self.get_char_stream(streamid) {
+                    Some(b'\n') => break,
+                    Some(ch) => line.push(ch),
+                    None if line.is_empty() => return lines,
+                    None => break,
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// read a stream of bytes until a NUL (0x00) byte, or until maxlen bytes have been read.
+    /// The NUL terminator is consumed, but is not included in the result
+    pub fn get_cstring_stream(&mut self, streamid: GlkStreamID, maxlen: usize) -> Vec<u8> {
+        let mut result = Vec::new();
+        while result.len() < maxlen {
+            match self.get_char_stream(streamid) {
+                Some(0) | None => break,
+                Some(ch) => result.push(ch),
+            }
+        }
+        result
+    }
+
     /// get a unicode character from a stream. If the stream is output-only, or if there
     /// are no more characters to read, return None
     pub fn get_char_stream_uni(&mut self, streamid: GlkStreamID) -> Option<char> {
@@ -214,36 +325,127 @@ impl<T: GlkWindow + Default> Glk<T> {
         }
     }
 
+    /// read the rest of a stream into a string. Unicode and text file streams are decoded
+    /// as they were written, and anything else is read as Latin-1. Returns None if the
+    /// stream does not exist or is output-only
+    pub fn read_to_string(&mut self, streamid: GlkStreamID) -> Option<String> {
+        self.stream_mgr.get(streamid)?.read_to_string()
+    }
+
+    /// returns the IO error that a file stream last ran into, which may explain why a read
+    /// came back short or empty. Each error is only returned once. Other streams never
+    /// have errors
+    pub fn stream_last_error(&mut self, streamid: GlkStreamID) -> Option<std::io::Error> {
+        self.stream_mgr.get(streamid)?.take_error()
+    }
+
     /*
      * Glk Section 5.3 - Closing Streams
      */
 
-    /// Closes a stream. Window streams are only close-able through glk.window_close()
+    /// Closes a stream. Window streams are only close-able through glk.window_close(). The
+    /// contents of a memory stream are handed back as bytes or codepoints
     pub fn stream_close(
         &mut self,
         streamid: GlkStreamID,
-    ) -> Option<(GlkStreamResult, Option<Vec<u8>>)> {
+    ) -> Option<(GlkStreamResult, StreamCloseData)> {
         let stream = self.stream_mgr.get(streamid)?;
         if stream.is_window_stream() {
             return None;
         }
 
-        let result = if stream.is_memory_stream() {
-            let result = stream.get_data();
-            Some((self.stream_mgr.close(streamid)?, Some(result)))
-        } else {
-            Some((self.stream_mgr.close(streamid)?, None))
-        };
+        let data = stream.close_data();
+        let result = Some((self.stream_mgr.close(streamid)?, data));
 
-        for win in self.window_iterate() {
-            if let Some(mut window) = self.win_mgr.get_ref(win) {
-                window.remove_echo_stream_if_matches(streamid);
-            }
-        }
+        self.win_mgr.remove_echo_stream(streamid);
 
         result
     }
 
+    /*
+     * Glk Section 5.5 - Styles
+     */
+
+    /// Change the style of text written to a stream from now on. Only window streams
+    /// show styles, but every stream remembers the style it was last given
+    pub fn set_style(&mut self, streamid: GlkStreamID, style: GlkStyle) {
+        self.stream_flush(streamid);
+        if let Some(stream) = self.stream_mgr.get(streamid) {
+            stream.set_style(style);
+        }
+    }
+
+    /// Returns the current style of a stream
+    pub fn stream_get_style(&mut self, streamid: GlkStreamID) -> Option<GlkStyle> {
+        Some(self.stream_mgr.get(streamid)?.get_style())
+    }
+
+    /*
+     * Glk Section 5.5.1 - Suggesting the Appearance of Styles
+     */
+
+    /// Suggest how a style should look in windows of the given type. Hints only apply to
+    /// windows which are opened after they are set
+    pub fn stylehint_set(
+        &mut self,
+        wintype: GlkWindowType,
+        style: GlkStyle,
+        hint: StyleHint,
+        value: i32,
+    ) {
+        self.stylehints
+            .entry(wintype)
+            .or_default()
+            .set(style, hint, value);
+    }
+
+    /// Remove a suggestion made by stylehint_set(), so that the back end's default is used
+    pub fn stylehint_clear(&mut self, wintype: GlkWindowType, style: GlkStyle, hint: StyleHint) {
+        if let Some(stylehints) = self.stylehints.get_mut(&wintype) {
+            stylehints.clear(style, hint);
+        }
+    }
+
+    /// Returns true if the hints for two styles in windows of the given type differ, so
+    /// that text in one style could be told apart from text in the other
+    pub fn style_distinguish(
+        &self,
+        wintype: GlkWindowType,
+        style1: GlkStyle,
+        style2: GlkStyle,
+    ) -> bool {
+        self.stylehints
+            .get(&wintype)
+            .is_some_and(|stylehints| stylehints.distinguish(style1, style2))
+    }
+
+    /// Get a copy of a memory stream's buffer without closing the stream. Returns None
+    /// for any other kind of stream
+    pub fn stream_peek_memory(&mut self, streamid: GlkStreamID) -> Option<Vec<u8>> {
+        let stream = self.stream_mgr.get(streamid)?;
+        stream.is_memory_stream().then(|| stream.get_data())
+    }
+
+    /// Get a copy of a unicode memory stream's codepoints without closing the stream.
+    /// Returns None for any other kind of stream
+    pub fn stream_peek_memory_uni(&mut self, streamid: GlkStreamID) -> Option<Vec<u32>> {
+        let stream = self.stream_mgr.get(streamid)?;
+        (stream.is_memory_stream() && stream.reads_unicode()).then(|| stream.get_data_uni())
+    }
+
+    /*
+     * Glk Section 9.1 - Creating Hyperlinks
+     */
+
+    /// Make text written to a stream from now on part of a hyperlink, or of no link if
+    /// linkval is 0. Only window streams show hyperlinks
+    pub fn set_hyperlink(&mut self, streamid: GlkStreamID, linkval: u32) {
+        self.stream_flush(streamid);
+        if let Some(stream) = self.stream_mgr.get(streamid) {
+            stream.set_hyperlink(linkval);
+        }
+    }
+
     /*
      * Glk Section 5.4 - Stream Positions
      */
@@ -281,6 +483,60 @@ impl<T: GlkWindow + Default> Glk<T> {
         self.stream_mgr.new_stream(mem_stream, file_mode)
     }
 
+    /// Open a memory-based buffer of unicode codepoints to do stream I/O. Each character
+    /// takes up one element of the buffer
+    pub fn stream_open_memory_uni(
+        &mut self,
+        buf: Vec<u32>,
+        file_mode: GlkFileMode,
+        _rock: GlkRock,
+    ) -> GlkStreamID {
+        let mem_stream = Rc::new(RefCell::new(MemStreamUni::new(buf)));
+        self.stream_mgr.new_stream(mem_stream, file_mode)
+    }
+
+    /*
+     * Glk Section 5.6.4 - Resource Streams
+     */
+
+    /// Attach a blorb file whose resources can be opened as streams
+    pub fn set_resource_file(&mut self, blorb: BlorbReader) {
+        self.resource_file = Some(Rc::new(blorb));
+    }
+
+    /// Open a read-only stream over a Data resource in the attached blorb file. The stream
+    /// reads from the blorb file itself rather than from its own copy of the resource
+    pub fn stream_open_resource(
+        &mut self,
+        resource_id: u32,
+        _rock: GlkRock,
+    ) -> Option<GlkStreamID> {
+        self.open_resource_stream(resource_id, false)
+    }
+
+    /// Open a read-only unicode stream over a Data resource. A TEXT resource is read as
+    /// UTF-8, and a BINA resource as four-byte big-endian characters
+    pub fn stream_open_resource_uni(
+        &mut self,
+        resource_id: u32,
+        _rock: GlkRock,
+    ) -> Option<GlkStreamID> {
+        self.open_resource_stream(resource_id, true)
+    }
+
+    fn open_resource_stream(&mut self, resource_id: u32, unicode: bool) -> Option<GlkStreamID> {
+        let blorb = self.resource_file.as_ref()?;
+        let resource_stream = Rc::new(RefCell::new(ResourceStream::new(
+            blorb,
+            resource_id as usize,
+            unicode,
+        )?));
+        Some(
+            self.stream_mgr
+                .new_stream(resource_stream, GlkFileMode::Read),
+        )
+    }
+
     /*
      * Glk Section 5.6.3 - File Streams
      */
@@ -292,15 +548,7 @@ impl<T: GlkWindow + Default> Glk<T> {
         mode: GlkFileMode,
         rock: GlkRock,
     ) -> Option<GlkStreamID> {
-        let fileref = self.fileref_mgr.get(filerefid)?;
-
-        let file_stream = if fileref.is_temp {
-            Rc::new(RefCell::new(FileStream::create_temp(fileref, rock)?))
-        } else {
-            Rc::new(RefCell::new(FileStream::open_file(fileref, mode, rock)?))
-        };
-
-        Some(self.stream_mgr.new_stream(file_stream, mode))
+        self.open_file_stream(filerefid, mode, rock, false)
     }
 
     /// open a file stream using unicode encoding. If opening in text mode, the file
@@ -308,20 +556,151 @@ impl<T: GlkWindow + Default> Glk<T> {
     /// and read as a four-byte big-endian value
     pub fn stream_open_file_uni(
         &mut self,
-        _fileref: GlkFileRef,
-        _mode: GlkFileMode,
-        _rock: GlkRock,
+        filerefid: GlkFileRef,
+        mode: GlkFileMode,
+        rock: GlkRock,
+    ) -> Option<GlkStreamID> {
+        self.open_file_stream(filerefid, mode, rock, true)
+    }
+
+    fn open_file_stream(
+        &mut self,
+        filerefid: GlkFileRef,
+        mode: GlkFileMode,
+        rock: GlkRock,
+        unicode: bool,
     ) -> Option<GlkStreamID> {
-        todo!();
+        let fileref = self.fileref_mgr.get(filerefid)?;
+
+        let mut file_stream = if fileref.is_temp {
+            FileStream::create_temp(fileref, rock)?
+        } else {
+            FileStream::open_file(fileref, mode, rock)?
+        };
+        file_stream.unicode = unicode;
+
+        Some(
+            self.stream_mgr
+                .new_stream(Rc::new(RefCell::new(file_stream)), mode),
+        )
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{windows::testwin::GlkTestWindow, GlkFileUsage};
+    use crate::{
+        gestalt::{Gestalt, GestaltResult},
+        windows::{
+            testwin::GlkTestWindow, GlkWindowType, WindowSplitAmount, WindowSplitMethod,
+            WindowSplitPosition,
+        },
+        GlkFileUsage,
+    };
+    use blorb::{testing::BlorbBuilder, types::ResourceType};
 
     use super::*;
 
+    fn blorb_with_data(data: &[u8]) -> BlorbReader {
+        blorb_with_chunk(b"BINA", data)
+    }
+
+    fn blorb_with_chunk(type_code: &[u8; 4], data: &[u8]) -> BlorbReader {
+        let bytes = BlorbBuilder::new()
+            .resource(ResourceType::Data, 3, type_code, data)
+            .build();
+        BlorbReader::new(bytes).expect("could not read blorb")
+    }
+
+    #[test]
+    fn resource_streams_share_the_blorb_file() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            glk.set_resource_file(blorb_with_data(b"first line\nsecond line"));
+            let stream = glk.stream_open_resource(3, 0).unwrap();
+
+            assert_eq!(b"first line".to_vec(), glk.get_line_stream(stream, None));
+            assert_eq!(b"second line".to_vec(), glk.get_buffer_stream(stream, None));
+
+            // the stream holds on to the reader itself, not a copy of the resource
+            let blorb = glk.resource_file.as_ref().unwrap();
+            assert_eq!(2, Rc::strong_count(blorb));
+
+            glk.stream_close(stream);
+            assert_eq!(1, Rc::strong_count(glk.resource_file.as_ref().unwrap()));
+        });
+    }
+
+    #[test]
+    fn cannot_open_a_missing_resource_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert!(glk.stream_open_resource(3, 0).is_none());
+            glk.set_resource_file(blorb_with_data(b"data"));
+            assert!(glk.stream_open_resource(4, 0).is_none());
+        });
+    }
+
+    #[test]
+    fn resource_streams_are_only_reported_with_a_resource_file() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert_eq!(
+                glk.gestalt(Gestalt::ResourceStream),
+                GestaltResult::CanAccept(false)
+            );
+            assert!(glk.stream_open_resource(3, 0).is_none());
+
+            glk.set_resource_file(blorb_with_data(b"data"));
+            assert_eq!(
+                glk.gestalt(Gestalt::ResourceStream),
+                GestaltResult::CanAccept(true)
+            );
+            assert!(glk.stream_open_resource(3, 0).is_some());
+        });
+    }
+
+    #[test]
+    fn invalid_utf8_in_resource_streams_is_replaced() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            glk.set_resource_file(blorb_with_chunk(b"TEXT", &[b'a', 0xff, b'b', 0xc3, b'c']));
+            let stream = glk.stream_open_resource_uni(3, 0).unwrap();
+            assert_eq!(
+                glk.get_buffer_stream_uni(stream, None),
+                "a\u{fffd}b\u{fffd}c"
+            );
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+        });
+    }
+
+    #[test]
+    fn can_read_resource_streams() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert_eq!(
+                glk.gestalt(Gestalt::ResourceStream),
+                GestaltResult::CanAccept(false)
+            );
+            glk.set_resource_file(blorb_with_chunk(b"TEXT", "\u{e9}t\u{e9}\nx".as_bytes()));
+            assert_eq!(
+                glk.gestalt(Gestalt::ResourceStream),
+                GestaltResult::CanAccept(true)
+            );
+
+            let stream = glk.stream_open_resource(3, 0).unwrap();
+            assert_eq!(glk.get_char_stream(stream), Some(0xc3));
+            glk.stream_close(stream);
+
+            let stream = glk.stream_open_resource_uni(3, 0).unwrap();
+            assert_eq!(glk.get_line_stream_uni(stream, None), "\u{e9}t\u{e9}");
+            assert_eq!(glk.stream_get_position(stream), Some(6));
+            assert_eq!(glk.get_char_stream_uni(stream), Some('x'));
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+            glk.stream_set_position(stream, 0, GlkSeekMode::Start);
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{e9}'));
+            glk.stream_close(stream);
+
+            glk.set_resource_file(blorb_with_data(&[0, 0, 0, 0xe9, 0, 0, 0, 0x78]));
+            let stream = glk.stream_open_resource_uni(3, 0).unwrap();
+            assert_eq!(glk.get_buffer_stream_uni(stream, None), "\u{e9}x");
+        });
+    }
+
     fn get_tmpdir() -> String {
         if let Ok(tmpdir) = std::env::var("TMPDIR") {
             tmpdir.to_string()
@@ -330,6 +709,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn temp_files_are_unique_and_kept_until_deleted() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let filerefs = (0..100)
+                .map(|rock| glk.fileref_create_temp(GlkFileUsage::Data, rock).unwrap())
+                .collect::<Vec<_>>();
+            let paths = filerefs
+                .iter()
+                .map(|fileref| glk.fileref_mgr.get(*fileref).unwrap().name.clone())
+                .collect::<std::collections::HashSet<_>>();
+            assert_eq!(paths.len(), 100);
+            assert!(paths.iter().all(|path| path.exists()));
+
+            for fileref in filerefs {
+                glk.fileref_delete_file(fileref);
+            }
+            assert!(paths.iter().all(|path| !path.exists()));
+        });
+    }
+
     #[test]
     fn can_open_a_file_and_write_to_it() {
         Glk::<GlkTestWindow>::start(|glk| {
@@ -411,6 +810,118 @@ mod test {
         });
     }
 
+    #[test]
+    fn can_print_to_the_current_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream = glk.stream_open_memory(vec![0u8; 8], GlkFileMode::Write, 45);
+            glk.stream_set_current(mem_stream);
+            glk.print(42);
+            glk.println('!');
+
+            let (result, data) = glk.stream_close(mem_stream).unwrap();
+            assert_eq!(result.write_count, 4);
+            assert_eq!(data, StreamCloseData::Bytes(b"42!\n\0\0\0\0".to_vec()));
+        });
+    }
+
+    #[test]
+    fn reading_an_output_only_stream_returns_nothing() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream = glk.stream_open_memory(b"abcd".to_vec(), GlkFileMode::Write, 45);
+            assert_eq!(glk.get_char_stream(mem_stream), None);
+            assert_eq!(glk.get_char_stream_uni(mem_stream), None);
+            assert!(glk.get_buffer_stream(mem_stream, None).is_empty());
+            assert!(glk.get_line_stream_uni(mem_stream, None).is_empty());
+
+            let (result, _) = glk.stream_close(mem_stream).unwrap();
+            assert_eq!(result.read_count, 0);
+        });
+    }
+
+    #[test]
+    fn writing_an_input_only_stream_does_nothing() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream = glk.stream_open_memory(b"abcd".to_vec(), GlkFileMode::Read, 45);
+            glk.put_char_stream(mem_stream, b'x');
+            glk.put_string_stream(mem_stream, "yz");
+
+            let (result, data) = glk.stream_close(mem_stream).unwrap();
+            assert_eq!(result.write_count, 0);
+            assert_eq!(data, StreamCloseData::Bytes(b"abcd".to_vec()));
+        });
+    }
+
+    #[test]
+    fn can_peek_at_a_memory_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream = glk.stream_open_memory(vec![0u8; 4], GlkFileMode::Write, 45);
+            glk.put_buffer_stream(mem_stream, b"ab");
+            assert_eq!(glk.stream_peek_memory(mem_stream), Some(b"ab\0\0".to_vec()));
+
+            glk.put_buffer_stream(mem_stream, b"cd");
+            assert_eq!(glk.stream_peek_memory(mem_stream), Some(b"abcd".to_vec()));
+
+            let (_, data) = glk.stream_close(mem_stream).unwrap();
+            assert_eq!(data, StreamCloseData::Bytes(b"abcd".to_vec()));
+            assert!(glk.stream_peek_memory(mem_stream).is_none());
+
+            let fileref = glk.fileref_create_temp(GlkFileUsage::Data, 23).unwrap();
+            let file_stream = glk
+                .stream_open_file(fileref, GlkFileMode::ReadWrite, 24)
+                .unwrap();
+            assert!(glk.stream_peek_memory(file_stream).is_none());
+        });
+    }
+
+    #[test]
+    fn can_read_several_lines_from_a_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream =
+                glk.stream_open_memory(b"one\n\nthree".to_vec(), GlkFileMode::Read, 45);
+
+            assert_eq!(
+                glk.get_lines_stream(mem_stream, 2),
+                vec![b"one".to_vec(), Vec::new()]
+            );
+            assert_eq!(glk.get_line_stream(mem_stream, None), b"three".to_vec());
+            assert!(glk.get_lines_stream(mem_stream, 2).is_empty());
+        });
+    }
+
+    #[test]
+    fn can_count_the_lines_read_from_a_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream =
+                glk.stream_open_memory(b"one\ntwo\nthree\n".to_vec(), GlkFileMode::Read, 45);
+
+            let mut counts = Vec::new();
+            while !glk.get_line_stream(mem_stream, None).is_empty() {
+                counts.push(glk.stream_line_count(mem_stream));
+            }
+            assert_eq!(counts, vec![1, 2, 3]);
+
+            // line breaks read by any other means count too
+            glk.stream_set_position(mem_stream, 0, GlkSeekMode::Start);
+            glk.get_buffer_stream(mem_stream, Some(5));
+            assert_eq!(glk.stream_line_count(mem_stream), 4);
+            glk.get_char_stream(mem_stream);
+            glk.get_char_stream(mem_stream);
+            glk.get_char_stream(mem_stream);
+            assert_eq!(glk.stream_line_count(mem_stream), 5);
+        });
+    }
+
+    #[test]
+    fn can_read_a_nul_terminated_string_from_a_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream = glk.stream_open_memory(b"abc\0def".to_vec(), GlkFileMode::Read, 45);
+
+            assert_eq!(glk.get_cstring_stream(mem_stream, 80), b"abc".to_vec());
+            assert_eq!(glk.get_char_stream(mem_stream), Some(b'd'));
+            assert_eq!(glk.get_cstring_stream(mem_stream, 1), b"e".to_vec());
+        });
+    }
+
     #[test]
     fn can_read_byte_buffer_from_stream() {
         Glk::<GlkTestWindow>::start(|glk| {
@@ -427,6 +938,24 @@ mod test {
         });
     }
 
+    #[test]
+    fn reading_past_end_of_memory_stream_returns_remaining_bytes() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let mem_stream = glk.stream_open_memory(
+                vec![b't', b'e', b's', b't', b'i', b'n', b'g'],
+                GlkFileMode::Read,
+                45,
+            );
+
+            glk.stream_set_position(mem_stream, 2, GlkSeekMode::Start);
+            assert_eq!(
+                glk.get_buffer_stream(mem_stream, Some(100)),
+                vec![b's', b't', b'i', b'n', b'g']
+            );
+            assert_eq!(glk.stream_get_position(mem_stream), Some(7));
+        });
+    }
+
     #[test]
     fn can_read_byte_from_stream() {
         Glk::<GlkTestWindow>::start(|glk| {
@@ -500,7 +1029,7 @@ mod test {
             if let Some((result, bytes)) = close {
                 assert_eq!(result.read_count, 3);
                 assert_eq!(result.write_count, 0);
-                assert_eq!(bytes, Some(vec![b't', b'e', b's', b't', b'i', b'n', b'g']));
+                assert_eq!(bytes, StreamCloseData::Bytes(b"testing".to_vec()));
             }
         });
     }
@@ -520,7 +1049,7 @@ mod test {
             assert!(response.is_some());
 
             if let Some((result, bytes)) = response {
-                assert!(bytes.is_none());
+                assert_eq!(bytes, StreamCloseData::None);
                 assert_eq!(result.read_count, 0);
                 assert_eq!(result.write_count, 30);
             }
@@ -539,6 +1068,382 @@ mod test {
         });
     }
 
+    #[test]
+    fn truncated_characters_at_the_end_of_a_file_are_not_read() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            // the file ends part way through the three bytes of a euro sign
+            let tmpfile = format!("{}/truncated_utf8.txt", get_tmpdir());
+            std::fs::write(&tmpfile, b"a\xe2\x82").unwrap();
+
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::TextMode, &tmpfile, 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('a'));
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+            glk.stream_close(stream);
+
+            // and here part way through a four byte character
+            let tmpfile = format!("{}/truncated_uni.bin", get_tmpdir());
+            std::fs::write(&tmpfile, [0, 0, 0, b'a', 0, 0]).unwrap();
+
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::BinaryMode, &tmpfile, 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file_uni(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('a'));
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+            glk.stream_close(stream);
+        });
+    }
+
+    #[test]
+    fn unicode_memory_streams_hold_one_codepoint_per_character() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let stream = glk.stream_open_memory_uni(vec![0; 4], GlkFileMode::ReadWrite, 23);
+            glk.put_char_stream_uni(stream, '\u{1f600}');
+            glk.put_string_stream(stream, "a\u{10348}");
+            assert_eq!(glk.stream_get_position(stream), Some(3));
+            assert_eq!(
+                glk.stream_peek_memory_uni(stream),
+                Some(vec![0x1f600, 0x61, 0x10348, 0])
+            );
+
+            glk.stream_set_position(stream, 0, GlkSeekMode::Start);
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{1f600}'));
+            assert_eq!(glk.get_char_stream(stream), Some(b'a'));
+            // characters outside of Latin-1 can't be read as bytes
+            assert_eq!(glk.get_char_stream(stream), Some(b'?'));
+            let (_, data) = glk.stream_close(stream).unwrap();
+            assert_eq!(
+                data,
+                StreamCloseData::Unicode(vec![0x1f600, 0x61, 0x10348, 0])
+            );
+
+            let stream = glk.stream_open_memory_uni(
+                vec![0x10000, 0x0a, 0x10ffff, 0x62],
+                GlkFileMode::Read,
+                24,
+            );
+            assert_eq!(glk.get_line_stream_uni(stream, None), "\u{10000}");
+            assert_eq!(glk.get_buffer_stream_uni(stream, None), "\u{10ffff}b");
+            glk.stream_close(stream);
+
+            let stream = glk.stream_open_memory(vec![0; 4], GlkFileMode::Write, 25);
+            assert_eq!(glk.stream_peek_memory_uni(stream), None);
+            glk.stream_close(stream);
+        });
+    }
+
+    #[test]
+    fn read_errors_can_be_retrieved() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            // a directory can be opened, but reading from it fails
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::BinaryMode, get_tmpdir(), 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert!(glk.stream_last_error(stream).is_none());
+
+            assert!(glk.get_buffer_stream(stream, None).is_empty());
+            let error = glk.stream_last_error(stream).expect("no error reported");
+            assert_eq!(error.kind(), std::io::ErrorKind::IsADirectory);
+            assert!(glk.stream_last_error(stream).is_none());
+            glk.stream_close(stream);
+
+            // reaching the end of a file is not an error
+            let tmpfile = format!("{}/last_error.txt", get_tmpdir());
+            std::fs::write(&tmpfile, "ab").unwrap();
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::BinaryMode, &tmpfile, 25)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 26)
+                .unwrap();
+            assert_eq!(glk.get_line_stream(stream, Some(10)), b"ab");
+            assert!(glk.stream_last_error(stream).is_none());
+            glk.stream_close(stream);
+            let _ = std::fs::remove_file(&tmpfile);
+
+            let stream = glk.stream_open_memory(vec![0; 4], GlkFileMode::Read, 27);
+            assert!(glk.stream_last_error(stream).is_none());
+        });
+    }
+
+    #[test]
+    fn can_read_a_whole_stream_into_a_string() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let tmpfile = format!("{}/read_to_string.txt", get_tmpdir());
+            std::fs::write(&tmpfile, "h\u{e9}llo \u{2192} \u{1f600}\nbye").unwrap();
+
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::TextMode, &tmpfile, 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('h'));
+            assert_eq!(
+                glk.read_to_string(stream).as_deref(),
+                Some("\u{e9}llo \u{2192} \u{1f600}\nbye")
+            );
+            assert_eq!(glk.read_to_string(stream).as_deref(), Some(""));
+            glk.stream_close(stream);
+
+            // byte streams are Latin-1
+            let stream = glk.stream_open_memory(vec![0x68, 0xe9], GlkFileMode::Read, 25);
+            assert_eq!(glk.read_to_string(stream).as_deref(), Some("h\u{e9}"));
+            glk.stream_close(stream);
+
+            let stream = glk.stream_open_memory(vec![0; 4], GlkFileMode::Write, 26);
+            assert_eq!(glk.read_to_string(stream), None);
+            glk.stream_close(stream);
+            let _ = std::fs::remove_file(&tmpfile);
+        });
+    }
+
+    #[test]
+    fn byte_order_mark_is_skipped_in_text_files() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let tmpfile = format!("{}/bom_file.txt", get_tmpdir());
+            std::fs::write(&tmpfile, "\u{feff}h\u{e9}llo").unwrap();
+
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::TextMode, &tmpfile, 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('h'));
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{e9}'));
+            glk.stream_close(stream);
+
+            // as it is when reading a line or a buffer first
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_line_stream_uni(stream, None), "h\u{e9}llo");
+            glk.stream_close(stream);
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_buffer_stream_uni(stream, Some(2)), "h\u{e9}");
+            glk.stream_close(stream);
+
+            // binary data is left alone
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::BinaryMode, &tmpfile, 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{feff}'));
+            glk.stream_close(stream);
+        });
+    }
+
+    #[test]
+    fn unicode_file_positions_count_bytes() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let fileref = glk.fileref_create_temp(GlkFileUsage::Data, 23).unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::ReadWrite, 24)
+                .unwrap();
+            glk.put_string_stream(stream, "\u{e9}t\u{e9}");
+            glk.stream_set_position(stream, 0, GlkSeekMode::Start);
+
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{e9}'));
+            assert_eq!(glk.stream_get_position(stream), Some(2));
+            assert_eq!(glk.get_char_stream_uni(stream), Some('t'));
+            assert_eq!(glk.stream_get_position(stream), Some(3));
+        });
+    }
+
+    #[test]
+    fn put_char_writes_a_raw_byte() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let tmpfile = format!("{}/put_char_file.bin", get_tmpdir());
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::Data, tmpfile.clone(), 23)
+                .unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Write, 24)
+                .unwrap();
+            glk.put_char_stream(stream, b'A');
+            glk.stream_close(stream);
+
+            assert_eq!(std::fs::read(tmpfile).unwrap(), vec![65]);
+        });
+    }
+
+    #[test]
+    fn file_positions_are_shared_by_reads_and_writes() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let fileref = glk.fileref_create_temp(GlkFileUsage::Data, 23).unwrap();
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::ReadWrite, 24)
+                .unwrap();
+            glk.put_buffer_stream(stream, b"hello");
+            assert_eq!(glk.stream_get_position(stream), Some(5));
+
+            glk.stream_set_position(stream, 0, GlkSeekMode::Start);
+            assert_eq!(glk.stream_get_position(stream), Some(0));
+            assert_eq!(glk.get_buffer_stream(stream, Some(5)), b"hello");
+
+            glk.stream_set_position(stream, 0, GlkSeekMode::Start);
+            assert_eq!(glk.get_char_stream(stream), Some(b'h'));
+            assert_eq!(glk.stream_get_position(stream), Some(1));
+            glk.put_char_stream(stream, b'J');
+            assert_eq!(glk.stream_get_position(stream), Some(2));
+
+            glk.stream_set_position(stream, -1, GlkSeekMode::Current);
+            assert_eq!(glk.get_char_stream(stream), Some(b'J'));
+            assert_eq!(glk.get_char_stream(stream), Some(b'l'));
+        });
+    }
+
+    #[test]
+    fn file_streams_count_each_open_separately() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let tmpfile = format!("{}/count_file.txt", get_tmpdir());
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::Data, tmpfile, 23)
+                .unwrap();
+
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Write, 24)
+                .unwrap();
+            glk.put_string_stream(stream, "hello");
+            let (result, _) = glk.stream_close(stream).unwrap();
+            assert_eq!(result.write_count, 5);
+
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::WriteAppend, 24)
+                .unwrap();
+            glk.put_buffer_stream(stream, b" world");
+            let (result, _) = glk.stream_close(stream).unwrap();
+            assert_eq!(result.write_count, 6);
+
+            let stream = glk
+                .stream_open_file(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_buffer_stream(stream, Some(100)), b"hello world");
+            let (result, _) = glk.stream_close(stream).unwrap();
+            assert_eq!(result.read_count, 11);
+            assert_eq!(result.write_count, 0);
+
+            glk.fileref_delete_file(fileref);
+        });
+    }
+
+    fn write_unicode_file(usage: GlkFileUsage, name: &str) -> Vec<u8> {
+        let tmpfile = format!("{}/{name}", get_tmpdir());
+        let path = tmpfile.clone();
+        Glk::<GlkTestWindow>::start(move |glk| {
+            let fileref = glk.fileref_create_by_name(usage, path, 23).unwrap();
+            let stream = glk
+                .stream_open_file_uni(fileref, GlkFileMode::Write, 24)
+                .unwrap();
+            glk.put_char_stream_uni(stream, '\u{e9}');
+            glk.stream_close(stream);
+
+            let stream = glk
+                .stream_open_file_uni(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{e9}'));
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+        });
+        std::fs::read(tmpfile).unwrap()
+    }
+
+    #[test]
+    fn can_append_to_a_unicode_file() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let tmpfile = format!("{}/uni_append.bin", get_tmpdir());
+            let fileref = glk
+                .fileref_create_by_name(GlkFileUsage::BinaryMode, tmpfile, 23)
+                .unwrap();
+
+            let stream = glk
+                .stream_open_file_uni(fileref, GlkFileMode::Write, 24)
+                .unwrap();
+            glk.put_char_stream_uni(stream, '\u{e9}');
+            glk.stream_close(stream);
+
+            let stream = glk
+                .stream_open_file_uni(fileref, GlkFileMode::WriteAppend, 24)
+                .unwrap();
+            assert_eq!(glk.stream_get_position(stream), Some(4));
+            glk.put_char_stream_uni(stream, '\u{263a}');
+            assert_eq!(glk.stream_get_position(stream), Some(8));
+            glk.stream_close(stream);
+
+            let stream = glk
+                .stream_open_file_uni(fileref, GlkFileMode::Read, 24)
+                .unwrap();
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{e9}'));
+            assert_eq!(glk.get_char_stream_uni(stream), Some('\u{263a}'));
+            assert_eq!(glk.get_char_stream_uni(stream), None);
+            glk.stream_close(stream);
+
+            glk.fileref_delete_file(fileref);
+        });
+    }
+
+    #[test]
+    fn unicode_binary_files_use_four_bytes_per_character() {
+        assert_eq!(
+            write_unicode_file(GlkFileUsage::BinaryMode, "uni_binary.bin"),
+            vec![0, 0, 0, 0xe9]
+        );
+    }
+
+    #[test]
+    fn unicode_text_files_use_utf8() {
+        assert_eq!(
+            write_unicode_file(GlkFileUsage::TextMode, "uni_text.txt"),
+            vec![0xc3, 0xa9]
+        );
+    }
+
+    #[test]
+    fn can_read_lines_and_buffers_from_unicode_files() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            for (usage, name) in [
+                (GlkFileUsage::BinaryMode, "uni_lines.bin"),
+                (GlkFileUsage::TextMode, "uni_lines.txt"),
+            ] {
+                let tmpfile = format!("{}/{name}", get_tmpdir());
+                let fileref = glk.fileref_create_by_name(usage, tmpfile, 23).unwrap();
+                let stream = glk
+                    .stream_open_file_uni(fileref, GlkFileMode::Write, 24)
+                    .unwrap();
+                glk.put_string_stream_uni(stream, "h\u{e9}llo\nw\u{263a}rld\n\u{1f600}!");
+                glk.stream_close(stream);
+
+                let stream = glk
+                    .stream_open_file_uni(fileref, GlkFileMode::Read, 24)
+                    .unwrap();
+                assert_eq!(glk.get_line_stream_uni(stream, None), "h\u{e9}llo\n");
+                assert_eq!(glk.get_line_stream_uni(stream, Some(2)), "w\u{263a}");
+                assert_eq!(glk.get_line_stream_uni(stream, Some(10)), "rld\n");
+                assert_eq!(glk.get_buffer_stream_uni(stream, Some(1)), "\u{1f600}");
+                assert_eq!(glk.get_buffer_stream_uni(stream, None), "!");
+                assert_eq!(glk.get_buffer_stream_uni(stream, None), "");
+                glk.stream_close(stream);
+
+                glk.fileref_delete_file(fileref);
+            }
+        });
+    }
+
     #[test]
     fn can_append_to_a_file() {
         Glk::<GlkTestWindow>::start(|glk| {
@@ -688,4 +1593,43 @@ mod test {
             // assert_eq!(input, "some trailing text?".to_string());
         });
     }
+
+    #[test]
+    fn can_close_a_stream_echoed_by_several_windows() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let method = WindowSplitMethod {
+                position: WindowSplitPosition::Below,
+                amount: WindowSplitAmount::Proportional(50),
+                border: false,
+            };
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 1)
+                .unwrap();
+            let win2 = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextBuffer,
+                    Some(method.clone()),
+                    2,
+                )
+                .unwrap();
+            let win3 = glk
+                .window_open(Some(win2), GlkWindowType::TextGrid, Some(method), 3)
+                .unwrap();
+
+            let mem_stream = glk.stream_open_memory(vec![0u8; 20], GlkFileMode::Write, 74);
+            for win in [win1, win2, win3] {
+                glk.window_set_echo_stream(win, Some(mem_stream));
+            }
+
+            assert!(glk.stream_close(mem_stream).is_some());
+            for win in glk.window_iterate() {
+                assert_eq!(glk.window_get_echo_stream(win), None);
+            }
+
+            // writing to the windows no longer echoes anywhere
+            let stream = glk.window_get_stream(win3).unwrap();
+            glk.put_string_stream(stream, "hi");
+        });
+    }
 }
@@ -1,11 +1,14 @@
 use std::{cell::RefCell, rc::Rc};
 
+use blorb::types::ResourceType;
+
 use crate::{
+    error::GlkError,
     prelude::GlkRock,
-    stream::{GlkStreamID, GlkStreamResult},
+    stream::{GlkStreamID, GlkStreamResult, StreamCloseData},
     windows::{
-        GlkWindow, GlkWindowID, GlkWindowSize, GlkWindowType, WindowRef, WindowSplitMethod,
-        WindowType,
+        GlkColor, GlkWindow, GlkWindowID, GlkWindowSize, GlkWindowType, WindowRef, WindowSplitKey,
+        WindowSplitMethod, WindowType,
     },
     Glk, GlkFileMode,
 };
@@ -17,46 +20,92 @@ impl<T: GlkWindow + Default> Glk<T> {
      * Glk Spec Section 3.2 - Window Opening, Closing, and Constraints
      */
 
-    /// create a new window
+    /// create a new window. Returns GlkError::InvalidWindow if the parent window does not exist
     pub fn window_open(
         &mut self,
         parent: Option<GlkWindowID>,
         wintype: GlkWindowType,
         method: Option<WindowSplitMethod>,
         rock: GlkRock,
-    ) -> Option<GlkWindowID> {
-        let wintype = match wintype {
-            GlkWindowType::Blank => WindowType::Blank,
-            GlkWindowType::TextBuffer => WindowType::TextBuffer,
-            GlkWindowType::TextGrid => WindowType::TextGrid,
-            GlkWindowType::Graphics => WindowType::Graphics,
-            GlkWindowType::Pair => return None,
-        };
+    ) -> Result<GlkWindowID, GlkError> {
+        self.window_open_keyed(parent, wintype, method, WindowSplitKey::NewWindow, rock)
+    }
+
+    /// create a new window, choosing whether the split amount is measured against the new
+    /// window or the window being split
+    pub fn window_open_keyed(
+        &mut self,
+        parent: Option<GlkWindowID>,
+        wintype: GlkWindowType,
+        method: Option<WindowSplitMethod>,
+        key: WindowSplitKey,
+        rock: GlkRock,
+    ) -> Result<GlkWindowID, GlkError> {
+        let wintype_requested = wintype.clone();
+        if wintype == GlkWindowType::Pair {
+            return Err(GlkError::InvalidWindowType);
+        }
+        let wintype = WindowType::from(wintype);
+
+        if method.as_ref().is_some_and(|method| !method.is_valid()) {
+            return Err(GlkError::InvalidSplitAmount);
+        }
 
         let new_win = if let Some(parent) = parent {
-            self.win_mgr.split(
-                parent,
-                method,
-                wintype,
-                self.command.as_ref().unwrap().clone(),
-                rock,
-            )
+            self.win_mgr
+                .split(
+                    parent,
+                    method,
+                    key,
+                    wintype,
+                    self.command.as_ref().unwrap().clone(),
+                    rock,
+                )
+                .ok_or(GlkError::InvalidWindow(parent))?
         } else {
             self.win_mgr
                 .open_window(wintype, self.command.as_ref().unwrap().clone(), rock)
-        }?;
+        };
+
+        let win = self
+            .win_mgr
+            .get_window(new_win)
+            .ok_or(GlkError::InvalidWindow(new_win))?;
+        if let Some(stylehints) = self.stylehints.get(&wintype_requested) {
+            win.set_stylehints(stylehints.clone());
+        }
 
-        let win = Rc::new(RefCell::new(self.win_mgr.get_window(new_win)?));
+        let win = Rc::new(RefCell::new(win));
         let stream_id = self.stream_mgr.new_stream(win, GlkFileMode::Write);
-        self.win_mgr.set_stream_id(new_win, stream_id)?;
+        self.win_mgr
+            .set_stream_id(new_win, stream_id)
+            .ok_or(GlkError::InvalidWindow(new_win))?;
 
-        Some(new_win)
+        Ok(new_win)
     }
 
-    /// close the given window and all of its children
+    /// create a new window, and make its stream the current default stream
+    pub fn window_open_as_default(
+        &mut self,
+        parent: Option<GlkWindowID>,
+        wintype: GlkWindowType,
+        method: Option<WindowSplitMethod>,
+        rock: GlkRock,
+    ) -> Result<GlkWindowID, GlkError> {
+        let win = self.window_open(parent, wintype, method, rock)?;
+        let stream = self
+            .window_get_stream(win)
+            .ok_or(GlkError::InvalidWindow(win))?;
+        self.stream_set_current(stream);
+        Ok(win)
+    }
+
+    /// close the given window and all of its children. The window's echo stream, if any,
+    /// is not closed
     pub fn window_close(&mut self, win: GlkWindowID) -> Option<GlkStreamResult> {
         let winref = self.win_mgr.get_ref(win)?;
         let stream = winref.get_stream();
+        self.stream_flush(stream);
 
         winref.send_message(GlkMessage::Close(win));
 
@@ -64,38 +113,88 @@ impl<T: GlkWindow + Default> Glk<T> {
         self.stream_mgr.close(stream)
     }
 
+    /// close the given window and all of its children, and also return the contents of the
+    /// window's echo stream if that is a memory stream. The echo stream itself stays open
+    pub fn window_close_with_echo(
+        &mut self,
+        win: GlkWindowID,
+    ) -> Option<(GlkStreamResult, StreamCloseData)> {
+        let echo = self.window_get_echo_stream(win);
+        let result = self.window_close(win)?;
+
+        let data = echo
+            .and_then(|echo| self.stream_mgr.get(echo))
+            .map_or(StreamCloseData::None, |stream| stream.close_data());
+
+        Some((result, data))
+    }
+
+    /// hold back text written to a window until it is flushed, instead of waiting for the
+    /// back end after every write. Turning buffering off flushes anything held back
+    pub fn window_set_buffered(&mut self, win: GlkWindowID, buffered: bool) {
+        let Some(winref) = self.win_mgr.get_ref(win) else {
+            return;
+        };
+        if !buffered {
+            self.stream_flush(winref.get_stream());
+        }
+        winref.set_buffered(buffered);
+    }
+
+    // send on the output held back by every buffered window
+    pub(crate) fn flush_windows(&mut self) {
+        for win in self.win_mgr.get_iter() {
+            self.flush_window(win);
+        }
+    }
+
+    // send on the output held back by one window, before something else is sent to it
+    fn flush_window(&mut self, win: GlkWindowID) {
+        if let Some(winref) = self.win_mgr.get_ref(win) {
+            self.stream_flush(winref.get_stream());
+        }
+    }
+
     /*
      * Glk Spec Section 3.3 - Changing Window Constraints
      */
 
+    /// get a handle to a window, for the calls that take a window reference. returns None
+    /// if the window does not exist
+    pub fn window_ref(&self, win: GlkWindowID) -> Option<WindowRef<T>> {
+        self.win_mgr.get_ref(win)
+    }
+
     /// get the actual size of the window, in its measurement system
     pub fn window_get_size(&self, win: &WindowRef<T>) -> GlkWindowSize {
         win.get_size()
     }
 
-    /// Get the size of the window in its measurement system (Glk Spec section 1.9)
+    /// change the constraints of a pair window. returns false if the window is not a pair
+    /// window or the method is invalid
     pub fn window_set_arrangement(
         &self,
         win: &WindowRef<T>,
         method: WindowSplitMethod,
         keywin: Option<&WindowRef<T>>,
-    ) {
-        win.set_arrangement(method, keywin);
+    ) -> bool {
+        method.is_valid() && win.set_arrangement(method, keywin)
     }
 
-    /// returns the constraints of the window
+    /// returns the constraints of a pair window, or None if the window is not a pair window
     pub fn window_get_arrangement(
         &self,
         win: GlkWindowID,
-    ) -> (Option<WindowSplitMethod>, Option<GlkWindowID>) {
-        if let Some(winref) = self.win_mgr.get_ref(win) {
-            if let Some((method, keywin)) = winref.get_arrangement() {
-                let keywin_id = keywin.map(|k| k.id());
-                return (Some(method), keywin_id);
-            }
-        }
+    ) -> Option<(WindowSplitMethod, Option<GlkWindowID>)> {
+        let (method, keywin) = self.win_mgr.get_ref(win)?.get_arrangement()?;
+        Some((method, keywin.map(|k| k.id())))
+    }
 
-        (None, None)
+    /// returns true if a pair window draws a border between its children, or None if the
+    /// window is not a pair window
+    pub fn window_has_border(&self, win: GlkWindowID) -> Option<bool> {
+        let (method, _) = self.window_get_arrangement(win)?;
+        Some(method.border)
     }
 
     /*
@@ -103,7 +202,8 @@ impl<T: GlkWindow + Default> Glk<T> {
      */
 
     /// Move the cursor in a text grid window (all other window types ignore this API)
-    pub fn window_move_cursor(&self, win: &WindowRef<T>, xpos: u32, ypos: u32) {
+    pub fn window_move_cursor(&mut self, win: &WindowRef<T>, xpos: u32, ypos: u32) {
+        self.stream_flush(win.get_stream());
         win.move_cursor(xpos, ypos);
     }
 
@@ -143,6 +243,11 @@ impl<T: GlkWindow + Default> Glk<T> {
         Some(win.get_type())
     }
 
+    /// is this a window that displays content (as opposed to a pair window)
+    pub fn window_is_leaf(&self, win: GlkWindowID) -> Option<bool> {
+        Some(self.window_get_type(win)? != GlkWindowType::Pair)
+    }
+
     /// get the parent for this window
     pub fn window_get_parent(&self, win: GlkWindowID) -> Option<GlkWindowID> {
         let win = self.win_mgr.get_ref(win)?;
@@ -165,8 +270,9 @@ impl<T: GlkWindow + Default> Glk<T> {
         self.win_mgr.get_root()
     }
 
-    /// clears the window
-    pub fn window_clear(&self, win: GlkWindowID) {
+    /// clears the window - the current style of the window's stream is not reset
+    pub fn window_clear(&mut self, win: GlkWindowID) {
+        self.flush_window(win);
         if let Some(win) = self.win_mgr.get_ref(win) {
             win.clear();
         }
@@ -178,6 +284,110 @@ impl<T: GlkWindow + Default> Glk<T> {
         Some(win.get_stream())
     }
 
+    /*
+     * Glk Spec Section 7.1 - Image Resources
+     */
+
+    /// When the back end cannot display images, show the description of each image that is
+    /// drawn in its window instead, or in a neighbouring text window
+    pub fn set_graphics_fallback(&mut self, enabled: bool) {
+        self.graphics_fallback = enabled;
+    }
+
+    /// draw an image resource in a window. returns true if the image was displayed, or if
+    /// its description was shown in its place
+    pub fn image_draw(&mut self, win: GlkWindowID, image: u32, val1: i32, val2: i32) -> bool {
+        let Some(winref) = self.win_mgr.get_ref(win) else {
+            return false;
+        };
+        let Some(blorb) = self.resource_file.clone() else {
+            return false;
+        };
+        if blorb
+            .get_resource(ResourceType::Pict, image as usize)
+            .is_err()
+        {
+            return false;
+        }
+
+        if T::capabilities().graphics {
+            self.stream_flush(winref.get_stream());
+            winref.send_message(GlkMessage::DrawImage {
+                winid: win,
+                image,
+                val1,
+                val2,
+            });
+            return true;
+        }
+
+        if !self.graphics_fallback {
+            return false;
+        }
+
+        let Some(textwin) = [Some(winref.make_clone()), winref.get_sibling()]
+            .into_iter()
+            .flatten()
+            .find(|w| {
+                matches!(
+                    w.get_type(),
+                    GlkWindowType::TextBuffer | GlkWindowType::TextGrid
+                )
+            })
+        else {
+            return false;
+        };
+
+        let text = blorb
+            .get_resource_description(ResourceType::Pict, image as usize)
+            .unwrap_or_else(|| format!("Image {image}"));
+        self.put_string_stream(textwin.get_stream(), &format!("[{text}]"));
+        true
+    }
+
+    /*
+     * Glk Spec Section 7.2 - Graphics in Graphics Windows
+     */
+
+    /// fill a rectangle of a graphics window with a color (other window types ignore this)
+    pub fn window_fill_rect(
+        &mut self,
+        win: GlkWindowID,
+        color: GlkColor,
+        left: i32,
+        top: i32,
+        width: u32,
+        height: u32,
+    ) {
+        self.flush_window(win);
+        if let Some(win) = self.win_mgr.get_ref(win) {
+            win.fill_rect(color, left, top, width, height);
+        }
+    }
+
+    /// fill a rectangle of a graphics window with its background color (other window
+    /// types ignore this)
+    pub fn window_erase_rect(
+        &mut self,
+        win: GlkWindowID,
+        left: i32,
+        top: i32,
+        width: u32,
+        height: u32,
+    ) {
+        self.flush_window(win);
+        if let Some(win) = self.win_mgr.get_ref(win) {
+            win.erase_rect(left, top, width, height);
+        }
+    }
+
+    /// set the background color of a graphics window (other window types ignore this)
+    pub fn window_set_background_color(&self, win: GlkWindowID, color: GlkColor) {
+        if let Some(win) = self.win_mgr.get_ref(win) {
+            win.set_background_color(color);
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn t_get_winref(&self, win: GlkWindowID) -> WindowRef<T> {
         self.win_mgr.get_ref(win).unwrap()
@@ -187,13 +397,30 @@ impl<T: GlkWindow + Default> Glk<T> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::windows::{testwin::GlkTestWindow, WindowSplitAmount, WindowSplitPosition};
+    use crate::events::GlkEvent;
+    use crate::style::{GlkStyle, StyleHint};
+    use crate::windows::{
+        testwin::{GlkTestWindow, BACKENDS_CREATED, CAPABILITIES},
+        GlkCapabilities, WindowSplitAmount, WindowSplitPosition,
+    };
+    use blorb::{testing::BlorbBuilder, BlorbReader};
 
     #[test]
     fn can_create_a_window() {
         Glk::<GlkTestWindow>::start(|glk| {
             let win = glk.window_open(None, GlkWindowType::TextBuffer, None, 73);
-            assert!(win.is_some());
+            assert!(win.is_ok());
+        });
+    }
+
+    #[test]
+    fn can_open_a_window_as_the_default_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert!(glk.stream_get_current().is_none());
+            let win = glk
+                .window_open_as_default(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            assert_eq!(glk.stream_get_current(), glk.window_get_stream(win));
         });
     }
 
@@ -213,7 +440,47 @@ mod test {
                 }),
                 84,
             );
-            assert!(win2.is_some());
+            assert!(win2.is_ok());
+        });
+    }
+
+    #[test]
+    fn cannot_split_a_non_existent_window() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            glk.window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let win = glk.window_open(
+                Some(999),
+                GlkWindowType::TextGrid,
+                Some(WindowSplitMethod {
+                    position: WindowSplitPosition::Above,
+                    amount: WindowSplitAmount::Proportional(40),
+                    border: false,
+                }),
+                84,
+            );
+            assert_eq!(win, Err(GlkError::InvalidWindow(999)));
+        });
+    }
+
+    #[test]
+    fn cannot_split_more_than_one_hundred_percent() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let win2 = glk.window_open(
+                Some(win),
+                GlkWindowType::TextGrid,
+                Some(WindowSplitMethod {
+                    position: WindowSplitPosition::Above,
+                    amount: WindowSplitAmount::Proportional(150),
+                    border: false,
+                }),
+                84,
+            );
+            assert_eq!(win2, Err(GlkError::InvalidSplitAmount));
+            assert!(glk.window_get_sibling(win).is_none());
         });
     }
 
@@ -359,6 +626,696 @@ mod test {
         });
     }
 
+    #[test]
+    fn can_key_a_split_to_the_existing_window() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let method = WindowSplitMethod {
+                position: WindowSplitPosition::Above,
+                amount: WindowSplitAmount::Fixed(3),
+                border: false,
+            };
+            let win2 = glk
+                .window_open_keyed(
+                    Some(win1),
+                    GlkWindowType::TextGrid,
+                    Some(method.clone()),
+                    WindowSplitKey::ExistingWindow,
+                    84,
+                )
+                .unwrap();
+
+            let pair = glk.window_get_parent(win2).unwrap();
+            assert_eq!(glk.window_get_arrangement(pair), Some((method, Some(win1))));
+        });
+    }
+
+    #[test]
+    fn can_tell_if_a_split_has_a_border() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let mut method = WindowSplitMethod {
+                position: WindowSplitPosition::Above,
+                amount: WindowSplitAmount::Fixed(3),
+                border: true,
+            };
+            let win2 = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextGrid,
+                    Some(method.clone()),
+                    84,
+                )
+                .unwrap();
+            let pair = glk.window_get_parent(win2).unwrap();
+
+            assert_eq!(glk.window_has_border(pair), Some(true));
+            assert_eq!(glk.window_has_border(win1), None);
+
+            method.border = false;
+            let pairref = glk.t_get_winref(pair);
+            assert!(glk.window_set_arrangement(&pairref, method, None));
+            assert_eq!(glk.window_has_border(pair), Some(false));
+        });
+    }
+
+    #[test]
+    fn a_split_has_one_pair_and_two_leaves() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let method = WindowSplitMethod {
+                position: WindowSplitPosition::Above,
+                amount: WindowSplitAmount::Fixed(3),
+                border: false,
+            };
+            let win2 = glk
+                .window_open(Some(win1), GlkWindowType::Graphics, Some(method), 84)
+                .unwrap();
+            let pair = glk.window_get_parent(win2).unwrap();
+
+            assert_eq!(glk.window_is_leaf(pair), Some(false));
+            assert_eq!(glk.window_is_leaf(win1), Some(true));
+            assert_eq!(glk.window_is_leaf(win2), Some(true));
+            assert_eq!(glk.window_is_leaf(pair + 100), None);
+        });
+    }
+
+    #[test]
+    fn can_only_arrange_pair_windows() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let method = WindowSplitMethod {
+                position: WindowSplitPosition::Below,
+                amount: WindowSplitAmount::Fixed(3),
+                border: false,
+            };
+            let win2 = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextGrid,
+                    Some(method.clone()),
+                    84,
+                )
+                .unwrap();
+            let pair = glk.window_get_parent(win2).unwrap();
+
+            let leaf = glk.t_get_winref(win1);
+            assert!(!glk.window_set_arrangement(&leaf, method.clone(), None));
+            assert_eq!(glk.window_get_arrangement(win1), None);
+
+            let pair = glk.t_get_winref(pair);
+            assert!(glk.window_set_arrangement(&pair, method, Some(&leaf)));
+        });
+    }
+
+    #[test]
+    fn can_put_char_at_grid_cursor() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextGrid, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            let winref = glk.t_get_winref(win);
+            glk.window_move_cursor(&winref, 3, 1);
+            glk.put_char_stream(stream, b'X');
+
+            let testwin = winref.winref.borrow();
+            let testwin = testwin.backend().borrow();
+            assert_eq!(testwin.grid[1][3], 'X');
+            assert_eq!(testwin.grid[0][3], ' ');
+            assert_eq!((testwin.cursor_x, testwin.cursor_y), (4, 1));
+            assert!(testwin.textdata.is_empty());
+        });
+    }
+
+    #[test]
+    fn clearing_a_grid_fills_it_with_spaces() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextGrid, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            let winref = glk.t_get_winref(win);
+            glk.put_string_stream(stream, "top");
+            glk.window_move_cursor(&winref, 5, 2);
+            glk.put_string_stream(stream, "mid");
+            glk.window_clear(win);
+
+            let testwin = winref.winref.borrow();
+            let testwin = testwin.backend().borrow();
+            assert_eq!(testwin.grid.len(), testwin.height as usize);
+            assert!(testwin
+                .grid
+                .iter()
+                .all(|row| row.len() == testwin.width as usize && row.iter().all(|&ch| ch == ' ')));
+            assert_eq!((testwin.cursor_x, testwin.cursor_y), (0, 0));
+        });
+    }
+
+    // the back end resizes the display, which it reports through the back end of the first
+    // window that was opened, and tells the game with an Arrange event
+    fn resize_display(glk: &mut Glk<GlkTestWindow>, first: GlkWindowID, width: u32, height: u32) {
+        {
+            let first = glk.t_get_winref(first);
+            let first = first.winref.borrow();
+            let mut backend = first.backend().borrow_mut();
+            backend.width = width;
+            backend.height = height;
+        }
+
+        let root = glk.window_get_root().unwrap();
+        glk.post_event(GlkEvent::Arrange { win: root });
+        assert_eq!(
+            glk.drain_events(None),
+            vec![GlkEvent::Arrange { win: root }]
+        );
+    }
+
+    #[test]
+    fn split_windows_follow_their_pair_window_size() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let win2 = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextBuffer,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Above,
+                        amount: WindowSplitAmount::Proportional(50),
+                        border: false,
+                    }),
+                    84,
+                )
+                .unwrap();
+            let first = win1;
+            let win1 = glk.t_get_winref(win1);
+            let win2 = glk.t_get_winref(win2);
+
+            resize_display(glk, first, 80, 24);
+            assert_eq!(glk.window_get_size(&win2).height, 12);
+            assert_eq!(glk.window_get_size(&win1).height, 12);
+
+            // the pair window is resized along with the display
+            resize_display(glk, first, 80, 50);
+            let size = glk.window_get_size(&win2);
+            assert_eq!((size.width, size.height), (80, 25));
+            assert_eq!(glk.window_get_size(&win1).height, 25);
+        });
+    }
+
+    #[test]
+    fn fixed_splits_are_measured_in_the_key_window_units() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::Graphics, None, 73)
+                .unwrap();
+            let grid = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextGrid,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Above,
+                        amount: WindowSplitAmount::Fixed(3),
+                        border: false,
+                    }),
+                    84,
+                )
+                .unwrap();
+            let graphics = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::Graphics,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Left,
+                        amount: WindowSplitAmount::Fixed(100),
+                        border: false,
+                    }),
+                    85,
+                )
+                .unwrap();
+
+            resize_display(glk, win1, 320, 200);
+            let grid = glk.t_get_winref(grid);
+            {
+                let grid = grid.winref.borrow();
+                grid.backend().borrow_mut().cell = GlkWindowSize {
+                    width: 8,
+                    height: 10,
+                };
+            }
+
+            // three rows of ten pixels for the grid, and a hundred pixels for the graphics
+            let size = glk.window_get_size(&grid);
+            assert_eq!((size.width, size.height), (40, 3));
+            let size = glk.window_get_size(&glk.t_get_winref(graphics));
+            assert_eq!((size.width, size.height), (100, 170));
+            let size = glk.window_get_size(&glk.t_get_winref(win1));
+            assert_eq!((size.width, size.height), (220, 170));
+        });
+    }
+
+    #[test]
+    fn window_types_convert_to_and_from_the_internal_types() {
+        for wintype in [
+            GlkWindowType::TextBuffer,
+            GlkWindowType::TextGrid,
+            GlkWindowType::Graphics,
+            GlkWindowType::Blank,
+            GlkWindowType::Pair,
+        ] {
+            let internal = WindowType::from(wintype.clone());
+            assert_eq!(GlkWindowType::try_from(internal).unwrap(), wintype);
+        }
+
+        assert!(matches!(
+            GlkWindowType::try_from(WindowType::Root),
+            Err(GlkError::InvalidWindowType)
+        ));
+    }
+
+    #[test]
+    fn can_get_a_window_ref_for_a_window() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+
+            let winref = glk.window_ref(win).unwrap();
+            assert_eq!(winref.id(), win);
+            let size = glk.window_get_size(&winref);
+            assert_eq!((size.width, size.height), (12, 32));
+
+            glk.window_close(win);
+            assert!(glk.window_ref(win).is_none());
+        });
+    }
+
+    #[test]
+    fn text_grids_are_measured_in_characters() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win1 = glk
+                .window_open(None, GlkWindowType::Graphics, None, 73)
+                .unwrap();
+            let grid = glk
+                .window_open(
+                    Some(win1),
+                    GlkWindowType::TextGrid,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Below,
+                        amount: WindowSplitAmount::Fixed(5),
+                        border: false,
+                    }),
+                    84,
+                )
+                .unwrap();
+
+            // the back end measures in pixels, with characters 6 wide and 12 high
+            resize_display(glk, win1, 640, 480);
+            let grid = glk.t_get_winref(grid);
+            grid.winref.borrow().backend().borrow_mut().cell = GlkWindowSize {
+                width: 6,
+                height: 12,
+            };
+
+            assert_eq!(
+                grid.get_raw_size(),
+                GlkWindowSize {
+                    width: 640,
+                    height: 60
+                }
+            );
+            assert_eq!(
+                glk.window_get_size(&grid),
+                GlkWindowSize {
+                    width: 106,
+                    height: 5
+                }
+            );
+            assert_eq!(
+                glk.window_get_size(&glk.t_get_winref(win1)),
+                GlkWindowSize {
+                    width: 640,
+                    height: 420
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn the_root_window_has_no_back_end() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let created = || BACKENDS_CREATED.with(|count| count.get());
+            let before = created();
+
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            assert_eq!(created(), before + 1);
+
+            // splitting creates both the new window and a pair window
+            glk.window_open(
+                Some(win),
+                GlkWindowType::TextGrid,
+                Some(WindowSplitMethod {
+                    position: WindowSplitPosition::Above,
+                    amount: WindowSplitAmount::Fixed(3),
+                    border: false,
+                }),
+                84,
+            )
+            .unwrap();
+            assert_eq!(created(), before + 3);
+        });
+    }
+
+    #[test]
+    fn newlines_reach_the_back_end_as_line_breaks() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            glk.put_string_stream(stream, "a\nb");
+        });
+        assert_eq!(backend.lines, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn window_sizes_can_be_copied_and_compared() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextGrid, None, 73)
+                .unwrap();
+            let win = glk.t_get_winref(win);
+
+            let size = glk.window_get_size(&win);
+            let copy = size;
+            assert_eq!(size, copy);
+            assert_eq!(glk.window_get_size(&win), size);
+            assert_ne!(
+                size,
+                GlkWindowSize {
+                    width: size.width + 1,
+                    height: size.height
+                }
+            );
+        });
+    }
+
+    fn blorb_with_image(description: Option<&str>) -> BlorbReader {
+        let mut builder =
+            BlorbBuilder::new().resource(ResourceType::Pict, 3, b"PNG ", &[1, 2, 3, 4]);
+        if let Some(text) = description {
+            let mut rdes = vec![0, 0, 0, 1];
+            rdes.extend_from_slice(b"Pict\0\0\0\x03");
+            rdes.extend_from_slice(&(text.len() as u32).to_be_bytes());
+            rdes.extend_from_slice(text.as_bytes());
+            builder = builder.chunk(b"RDes", &rdes);
+        }
+        BlorbReader::new(builder.build()).expect("could not read blorb")
+    }
+
+    #[test]
+    fn text_only_back_ends_can_show_image_descriptions() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            CAPABILITIES.with(|caps| {
+                caps.set(GlkCapabilities {
+                    graphics: false,
+                    ..caps.get()
+                })
+            });
+            glk.set_resource_file(blorb_with_image(Some("a brass lamp")));
+            let text = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let graphics = glk
+                .window_open(
+                    Some(text),
+                    GlkWindowType::Graphics,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Above,
+                        amount: WindowSplitAmount::Proportional(50),
+                        border: false,
+                    }),
+                    74,
+                )
+                .unwrap();
+
+            assert!(!glk.image_draw(text, 3, 0, 0));
+            glk.set_graphics_fallback(true);
+            assert!(glk.image_draw(text, 3, 0, 0));
+            assert!(glk.image_draw(graphics, 3, 10, 10));
+            assert!(!glk.image_draw(text, 4, 0, 0));
+
+            glk.set_resource_file(blorb_with_image(None));
+            assert!(glk.image_draw(text, 3, 0, 0));
+        });
+        assert_eq!(backend.lines, vec!["[a brass lamp][a brass lamp][Image 3]"]);
+    }
+
+    #[test]
+    fn only_graphics_windows_can_be_drawn_in() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::Graphics, None, 73)
+                .unwrap();
+            let text = glk
+                .window_open(
+                    Some(win),
+                    GlkWindowType::TextBuffer,
+                    Some(WindowSplitMethod {
+                        position: WindowSplitPosition::Above,
+                        amount: WindowSplitAmount::Proportional(50),
+                        border: false,
+                    }),
+                    74,
+                )
+                .unwrap();
+
+            glk.window_fill_rect(win, GlkColor(0xff0000), 1, 2, 3, 4);
+            glk.window_set_background_color(win, GlkColor(0x00ff00));
+            glk.window_erase_rect(win, 5, 6, 7, 8);
+
+            // text windows ignore graphics requests
+            glk.window_fill_rect(text, GlkColor(0x0000ff), 0, 0, 1, 1);
+            glk.window_set_background_color(text, GlkColor(0x0000ff));
+
+            let win = glk.t_get_winref(win);
+            let win = win.winref.borrow();
+            let backend = win.backend().borrow();
+            assert_eq!(backend.background, GlkColor(0x00ff00));
+            assert_eq!(
+                backend.rects,
+                vec![
+                    (GlkColor(0xff0000), 1, 2, 3, 4),
+                    (GlkColor(0x00ff00), 5, 6, 7, 8)
+                ]
+            );
+
+            let text = glk.t_get_winref(text);
+            let text = text.winref.borrow();
+            let backend = text.backend().borrow();
+            assert_eq!(backend.background, GlkColor::default());
+            assert!(backend.rects.is_empty());
+        });
+    }
+
+    #[test]
+    fn buffered_writes_do_not_wait_for_the_back_end() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            glk.window_set_buffered(win, true);
+
+            let wr = glk.stream_mgr.get(stream).unwrap().put_string("hello ");
+            assert!(!wr.wait_needed);
+            assert_eq!(wr.len, 0);
+            glk.put_string_stream(stream, "world");
+            assert_eq!(glk.stream_flush(stream), 11);
+            assert_eq!(glk.stream_flush(stream), 0);
+
+            // changing the style sends the text written in the old style first
+            glk.put_string_stream(stream, "a");
+            glk.set_style(stream, GlkStyle::Emphasized);
+            glk.put_string_stream(stream, "b");
+            glk.window_set_buffered(win, false);
+            glk.put_string_stream(stream, "c");
+
+            assert_eq!(glk.window_close(win).unwrap().write_count, 14);
+        });
+        assert_eq!(
+            backend.styled,
+            vec![
+                (GlkStyle::Normal, "hello world".to_string()),
+                (GlkStyle::Normal, "a".to_string()),
+                (GlkStyle::Emphasized, "b".to_string()),
+                (GlkStyle::Emphasized, "c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_filter_changes_text_sent_to_windows() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            glk.set_output_filter(Box::new(|ch| match ch {
+                '@' => Some("AT".to_string()),
+                '#' => None,
+                ch => Some(ch.to_string()),
+            }));
+
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            let mem_stream = glk.stream_open_memory(vec![0u8; 8], GlkFileMode::Write, 74);
+            glk.window_set_echo_stream(win, Some(mem_stream));
+
+            glk.put_string_stream(stream, "a@b#");
+            glk.put_char_stream(stream, b'@');
+
+            // the echo stream sees the filtered text, but text written straight to other
+            // streams is left as it was written
+            glk.window_set_echo_stream(win, None);
+            glk.put_string_stream(mem_stream, "@");
+            let (_, data) = glk.stream_close(mem_stream).unwrap();
+            assert_eq!(data, StreamCloseData::Bytes(b"aATbAT@\0".to_vec()));
+        });
+        assert_eq!(backend.lines, vec!["aATbAT"]);
+    }
+
+    #[test]
+    fn text_is_tagged_with_the_current_hyperlink() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            glk.put_string_stream(stream, "go ");
+            glk.set_hyperlink(stream, 7);
+            glk.put_string_stream(stream, "north");
+            glk.set_hyperlink(stream, 0);
+            glk.put_string_stream(stream, " or ");
+            glk.set_hyperlink(stream, 8);
+            glk.put_string_stream(stream, "south");
+        });
+        assert_eq!(
+            backend.linked,
+            vec![
+                (0, "go ".to_string()),
+                (7, "north".to_string()),
+                (0, " or ".to_string()),
+                (8, "south".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn text_is_tagged_with_the_current_style() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            glk.put_string_stream(stream, "plain ");
+            glk.set_style(stream, GlkStyle::Emphasized);
+            glk.put_string_stream(stream, "loud");
+            glk.set_style(stream, GlkStyle::Normal);
+            glk.put_string_stream(stream, " plain");
+            assert_eq!(glk.stream_get_style(stream), Some(GlkStyle::Normal));
+        });
+        assert_eq!(
+            backend.styled,
+            vec![
+                (GlkStyle::Normal, "plain ".to_string()),
+                (GlkStyle::Emphasized, "loud".to_string()),
+                (GlkStyle::Normal, " plain".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn windows_keep_the_style_hints_they_were_opened_with() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let buffer = GlkWindowType::TextBuffer;
+            assert!(!glk.style_distinguish(buffer.clone(), GlkStyle::Normal, GlkStyle::Emphasized));
+
+            glk.stylehint_set(buffer.clone(), GlkStyle::Emphasized, StyleHint::Weight, 1);
+            assert!(glk.style_distinguish(buffer.clone(), GlkStyle::Normal, GlkStyle::Emphasized));
+            assert!(!glk.style_distinguish(
+                GlkWindowType::TextGrid,
+                GlkStyle::Normal,
+                GlkStyle::Emphasized
+            ));
+
+            let win = glk.window_open(None, buffer.clone(), None, 73).unwrap();
+            glk.stylehint_clear(buffer.clone(), GlkStyle::Emphasized, StyleHint::Weight);
+            assert!(!glk.style_distinguish(buffer, GlkStyle::Normal, GlkStyle::Emphasized));
+
+            let win = glk.t_get_winref(win);
+            assert_eq!(
+                win.get_stylehint(GlkStyle::Emphasized, StyleHint::Weight),
+                Some(1)
+            );
+            assert_eq!(win.get_stylehint(GlkStyle::Normal, StyleHint::Weight), None);
+        });
+    }
+
+    #[test]
+    fn clearing_a_window_sends_its_buffered_text_first() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            glk.window_set_buffered(win, true);
+
+            glk.put_string_stream(stream, "before");
+            glk.window_clear(win);
+            // the text reached the back end before the window was cleared
+            assert_eq!(glk.stream_flush(stream), 0);
+
+            glk.put_string_stream(stream, "after");
+            assert_eq!(glk.stream_flush(stream), 5);
+        });
+        assert_eq!(
+            backend.styled,
+            vec![
+                (GlkStyle::Normal, "before".to_string()),
+                (GlkStyle::Normal, "after".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn clearing_a_window_keeps_its_style() {
+        let backend = GlkTestWindow::run_backend(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+            glk.set_style(stream, GlkStyle::Emphasized);
+            glk.window_clear(win);
+            glk.put_string_stream(stream, "still loud");
+            assert_eq!(glk.stream_get_style(stream), Some(GlkStyle::Emphasized));
+        });
+        assert_eq!(
+            backend.styled,
+            vec![(GlkStyle::Emphasized, "still loud".to_string())]
+        );
+    }
+
     #[test]
     fn can_put_byte_style_char_into_window() {
         Glk::<GlkTestWindow>::start(|glk| {
@@ -368,7 +1325,7 @@ mod test {
             let stream = glk.window_get_stream(win).unwrap();
             glk.put_char_stream(stream, b'x');
             let winref = glk.t_get_winref(win);
-            assert_eq!(winref.winref.borrow().window.borrow().textdata, "x");
+            assert_eq!(winref.winref.borrow().backend().borrow().textdata, "x");
         });
     }
 
@@ -400,8 +1357,8 @@ mod test {
 
             let win1 = glk.t_get_winref(win1);
             let win2 = glk.t_get_winref(win2);
-            assert_eq!(win1.winref.borrow().window.borrow().textdata, "A");
-            assert_eq!(win2.winref.borrow().window.borrow().textdata, "B");
+            assert_eq!(win1.winref.borrow().backend().borrow().textdata, "A");
+            assert_eq!(win2.winref.borrow().backend().borrow().textdata, "B");
         });
     }
 
@@ -415,7 +1372,7 @@ mod test {
             glk.put_string_stream(stream, "hello, world!");
             let win = glk.t_get_winref(win);
             assert_eq!(
-                win.winref.borrow().window.borrow().textdata,
+                win.winref.borrow().backend().borrow().textdata,
                 "hello, world!"
             );
         });
@@ -430,7 +1387,7 @@ mod test {
             let stream = glk.window_get_stream(win).unwrap();
             glk.put_buffer_stream(stream, &[b'0', b'1', b'2', b'3']);
             let win = glk.t_get_winref(win);
-            assert_eq!(win.winref.borrow().window.borrow().textdata, "0123");
+            assert_eq!(win.winref.borrow().backend().borrow().textdata, "0123");
         });
     }
 
@@ -443,7 +1400,7 @@ mod test {
             let stream = glk.window_get_stream(win).unwrap();
             glk.put_char_stream_uni(stream, 'q');
             let win = glk.t_get_winref(win);
-            assert_eq!(win.winref.borrow().window.borrow().textdata, "q");
+            assert_eq!(win.winref.borrow().backend().borrow().textdata, "q");
         });
     }
 
@@ -456,7 +1413,7 @@ mod test {
             let stream = glk.window_get_stream(win).unwrap();
             glk.put_buffer_stream_uni(stream, &['q', 'r', 's', 't', 'u', 'v']);
             let win = glk.t_get_winref(win);
-            assert_eq!(win.winref.borrow().window.borrow().textdata, "qrstuv");
+            assert_eq!(win.winref.borrow().backend().borrow().textdata, "qrstuv");
         });
     }
 
@@ -511,17 +1468,55 @@ mod test {
 
             let win1 = glk.t_get_winref(win1);
             assert_eq!(
-                win1.winref.borrow().window.borrow().textdata,
+                win1.winref.borrow().backend().borrow().textdata,
                 "Above the sky"
             );
             let win2 = glk.t_get_winref(win2);
             assert_eq!(
-                win2.winref.borrow().window.borrow().textdata,
+                win2.winref.borrow().backend().borrow().textdata,
                 "Below ground. Look!"
             );
         });
     }
 
+    #[test]
+    fn can_retrieve_echo_stream_data_on_close() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let win_stream = glk.window_get_stream(win).unwrap();
+            let mem_stream = glk.stream_open_memory(vec![0u8; 20], GlkFileMode::Write, 74);
+            glk.window_set_echo_stream(win, Some(mem_stream));
+
+            glk.put_string_stream(win_stream, "hello");
+
+            let (_, echo) = glk.window_close_with_echo(win).unwrap();
+            let StreamCloseData::Bytes(echo) = echo else {
+                panic!("window_close_with_echo() did not return the echo bytes");
+            };
+            assert_eq!(&echo[0..5], b"hello");
+
+            // the echo stream is still open
+            assert!(glk.stream_close(mem_stream).is_some());
+        });
+    }
+
+    #[test]
+    fn can_count_chars_written_to_a_buffer_window() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            let stream = glk.window_get_stream(win).unwrap();
+
+            glk.put_string_stream(stream, "hello");
+            glk.put_char_stream(stream, b'!');
+            let stream_results = glk.window_close(win).unwrap();
+            assert_eq!(stream_results.write_count, 6);
+        });
+    }
+
     #[test]
     fn can_count_chars_in_output() {
         Glk::<GlkTestWindow>::start(|glk| {
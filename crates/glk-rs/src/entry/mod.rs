@@ -1,26 +1,37 @@
 mod glk_clock;
 mod glk_event;
+mod glk_schannel;
 mod glk_stream;
 mod glk_win;
 
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
 use std::path::Path;
+use std::rc::Rc;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 
+use blorb::BlorbReader;
 use unicode_normalization::UnicodeNormalization;
 
-use crate::events::{EventManager, GlkEvent};
+use crate::events::{EventManager, GlkEvent, RecordedInput};
 use crate::file_stream::{FileRefManager, GlkFileRef};
 use crate::gestalt::OutputType;
 use crate::keycode::Keycode;
 use crate::prelude::GlkRock;
+use crate::schannel::SoundChannelManager;
 use crate::stream::{GlkStreamID, StreamManager};
-use crate::windows::{GlkWindow, GlkWindowID, GlkWindowType, WindowManager, WindowSplitMethod};
+use crate::style::{GlkStyle, StyleHints};
+use crate::windows::{
+    GlkWindow, GlkWindowID, GlkWindowType, WindowManager, WindowSplitKey, WindowSplitMethod,
+};
 use crate::{gestalt::*, GlkFileUsage};
 
 /// A request from the glk library to the window code for something to happen
 pub enum GlkMessage {
-    /// write a string to a stdio stream or window
+    /// write a string to a stdio stream or window. a '\n' in the message ends the current
+    /// line, and the back end must start a new line rather than display the character.
+    /// text is passed along as it was written, so one message may hold several lines
     Write {
         /// winid: the window to write to
         winid: GlkWindowID,
@@ -44,10 +55,43 @@ pub enum GlkMessage {
 
         /// The configuration of the new window
         method: Option<WindowSplitMethod>,
+
+        /// Which of the two windows the split amount applies to
+        key: WindowSplitKey,
     },
 
     /// close a window and all of its children
     Close(GlkWindowID),
+
+    /// text written to the window from now on should be shown in the given style
+    SetStyle {
+        /// winid: the window whose style is changing
+        winid: GlkWindowID,
+        /// style: the new style
+        style: GlkStyle,
+    },
+
+    /// text written to the window from now on is part of the given hyperlink, or of no
+    /// link if linkval is 0
+    SetHyperlink {
+        /// winid: the window whose text is being linked
+        winid: GlkWindowID,
+        /// linkval: the value which identifies the link
+        linkval: u32,
+    },
+
+    /// draw an image resource in a window. for graphics windows, val1 and val2 are the
+    /// position of the image; for text buffers, val1 is its alignment
+    DrawImage {
+        /// winid: the window to draw in
+        winid: GlkWindowID,
+        /// image: the resource number of the image
+        image: u32,
+        /// val1: the x position or alignment of the image
+        val1: i32,
+        /// val2: the y position of the image
+        val2: i32,
+    },
 }
 
 /// The result of a request from glk
@@ -84,13 +128,8 @@ pub enum GlkResult {
 /// - glk_window_get_size(window_id)
 /// - glk_window_get/set_arrangement(window_id[, window_info]) -> WindowInfo
 /// - glk_window_clear(window_id)
-/// - glk_request_char_event(window_id)     -- & char_event_uni()?
-/// - glk_cancel_char_event(window_id)
 /// - glk_request_line_event(window_id)     -- & line_event_uni()?
 /// - glk_cancel_line_event(window_id)
-/// - glk_set_echo_line_event(window_id, bool)
-/// - glk_set_terminators_line_event(window_id, Vec<keycode>)
-/// - glk_request/cancel_mouse_event(window_id)
 /// - glk_put_char(window_id, ch)
 /// - glk_put_string(window_id, string)     -- & put_buffer()?
 /// - glk_set_style(window_id, style)
@@ -98,15 +137,8 @@ pub enum GlkResult {
 /// - glk_stylehint_clear(window_id, style, hint)
 /// - glk_style_distinguish(window_id, style1, style2)
 /// - glk_style_measure(window_id, style, hint) -> MeasurementResult
-/// - glk_image_draw(window_id, image, pos)     -- [pos: x/y coords, or ImageAlign value]
 /// - glk_image_draw_scaled(window_id, image, pos, scale)
-/// - glk_window_set_background_color(window_id, color)
-/// - glk_window_fill_rect(window_id, color, rect)
-/// - glk_window_erase_rect(window_id, rect)
 /// - glk_window_flow_break(window_id)
-/// - glk_set_hyperlink(window_id, linkval)
-/// - glk_request_hyperlink_event(window_id)
-/// - glk_cancel_hyperlink_event(window_id)
 
 #[derive(Default)]
 pub struct Glk<T: GlkWindow + Default + 'static> {
@@ -114,7 +146,14 @@ pub struct Glk<T: GlkWindow + Default + 'static> {
     event_mgr: EventManager,
     stream_mgr: StreamManager,
     fileref_mgr: FileRefManager,
+    schannel_mgr: SoundChannelManager,
     default_stream: Option<GlkStreamID>,
+    resource_file: Option<Rc<BlorbReader>>,
+    stylehints: HashMap<GlkWindowType, StyleHints>,
+    output_filter: Option<Box<dyn Fn(char) -> Option<String>>>,
+    graphics_fallback: bool,
+    input_log: Option<File>,
+    replay: VecDeque<RecordedInput>,
     command: Option<Sender<GlkMessage>>,
     response: Option<Receiver<GlkResult>>,
 }
@@ -166,26 +205,45 @@ impl<T: GlkWindow + Default + 'static> Glk<T> {
             Gestalt::CharOutput(Keycode::Basic(ch)) => {
                 if ch.is_glk_char() {
                     GestaltResult::CharOutput(OutputType::ExactPrint)
+                } else if let Some(approx) = transliterate(ch) {
+                    GestaltResult::CharOutput(
+                        OutputType::ApproxPrint(approx.chars().count() as u32),
+                    )
                 } else {
                     GestaltResult::CharOutput(OutputType::CannotPrint(1))
                 }
             }
             Gestalt::CharOutput(_) => GestaltResult::CharOutput(OutputType::CannotPrint(1)),
-            Gestalt::Unicode | Gestalt::UnicodeNorm => GestaltResult::CanAccept(true),
+            Gestalt::Unicode
+            | Gestalt::UnicodeNorm
+            | Gestalt::Timer
+            | Gestalt::DateTime
+            | Gestalt::LineInputEcho
+            | Gestalt::LineTerminators => GestaltResult::CanAccept(true),
+            Gestalt::ResourceStream => GestaltResult::CanAccept(self.resource_file.is_some()),
+            Gestalt::LineTerminatorKey(key) => GestaltResult::CanAccept(key.is_line_terminator()),
+            Gestalt::Graphics => GestaltResult::CanAccept(T::capabilities().graphics),
+            Gestalt::Sound | Gestalt::Sound2 => GestaltResult::CanAccept(T::capabilities().sound),
+            Gestalt::MouseInput => GestaltResult::CanAccept(T::capabilities().mouse_input),
+            Gestalt::Hyperlinks | Gestalt::HyperlinkInput => {
+                GestaltResult::CanAccept(T::capabilities().hyperlinks)
+            }
             _ => GestaltResult::CanAccept(false),
         }
     }
 
-    /// Convert a latin-1 / unicode character to lowercase
+    /// Convert a latin-1 / unicode character to lowercase. A character whose lowercase
+    /// form takes more than one character is returned unchanged
     pub fn char_to_lower(&self, ch: impl ToChar) -> char {
         let ch = ch.to_char();
-        ch.to_lowercase().next().unwrap()
+        single_char(ch.to_lowercase()).unwrap_or(ch)
     }
 
-    /// Convert a latin-1 / unicode character to uppercase
+    /// Convert a latin-1 / unicode character to uppercase. A character whose uppercase
+    /// form takes more than one character, like 'ß', is returned unchanged
     pub fn char_to_upper(&self, ch: impl ToChar) -> char {
         let ch = ch.to_char();
-        ch.to_uppercase().next().unwrap()
+        single_char(ch.to_uppercase()).unwrap_or(ch)
     }
 
     /// convert a string to upper case
@@ -208,13 +266,14 @@ impl<T: GlkWindow + Default + 'static> Glk<T> {
 
         let mut iter = s.chars();
 
+        // the uppercase form of a character may be several characters, like "SS" for 'ß'
         let first_char = iter.next().unwrap();
-        result.push(first_char.to_uppercase().next().unwrap());
+        result.extend(first_char.to_uppercase());
 
         if style == TitleCaseStyle::UppercaseFirst {
-            result.extend(iter);
+            result.push_str(iter.as_str());
         } else {
-            result.extend(iter.map(|x| x.to_lowercase().next().unwrap()));
+            result.push_str(&iter.as_str().to_lowercase());
         }
 
         result
@@ -269,6 +328,12 @@ impl<T: GlkWindow + Default + 'static> Glk<T> {
     }
 }
 
+// the only character of a case mapping, or None if it maps to several characters
+fn single_char(mut mapping: impl Iterator<Item = char>) -> Option<char> {
+    let ch = mapping.next()?;
+    mapping.next().is_none().then_some(ch)
+}
+
 /// determines the style of title case conversions
 #[derive(Debug, PartialEq)]
 pub enum TitleCaseStyle {
@@ -300,7 +365,7 @@ impl ToChar for char {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::windows::testwin::GlkTestWindow;
+    use crate::{stream::StreamCloseData, windows::testwin::GlkTestWindow, GlkFileMode};
 
     #[test]
     fn can_get_glk_version() {
@@ -312,11 +377,148 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_window_reports_its_capabilities() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            for gestalt in [
+                Gestalt::Timer,
+                Gestalt::DateTime,
+                Gestalt::LineInputEcho,
+                Gestalt::LineTerminators,
+                Gestalt::LineTerminatorKey(Keycode::Escape),
+                Gestalt::Graphics,
+                Gestalt::MouseInput,
+                Gestalt::Hyperlinks,
+                Gestalt::HyperlinkInput,
+            ] {
+                assert_eq!(GestaltResult::CanAccept(true), glk.gestalt(gestalt));
+            }
+
+            for gestalt in [
+                Gestalt::Sound,
+                Gestalt::Sound2,
+                Gestalt::SoundMusic,
+                Gestalt::GraphicsTransparency,
+                Gestalt::LineTerminatorKey(Keycode::Return),
+                // no resource file has been set
+                Gestalt::ResourceStream,
+            ] {
+                assert_eq!(GestaltResult::CanAccept(false), glk.gestalt(gestalt));
+            }
+        });
+    }
+
+    #[test]
+    fn gestalt_answers_input_and_version_queries() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert_eq!(
+                GestaltResult::Version(0x00000705),
+                glk.gestalt(Gestalt::Version)
+            );
+            assert_eq!(
+                GestaltResult::CanAccept(true),
+                glk.gestalt(Gestalt::LineInput('a'))
+            );
+            assert_eq!(
+                GestaltResult::CanAccept(false),
+                glk.gestalt(Gestalt::LineInput('\u{7}'))
+            );
+            assert_eq!(
+                GestaltResult::CanAccept(true),
+                glk.gestalt(Gestalt::CharInput(Keycode::Basic('a')))
+            );
+            assert_eq!(
+                GestaltResult::CanAccept(true),
+                glk.gestalt(Gestalt::CharInput(Keycode::Return))
+            );
+        });
+    }
+
+    #[test]
+    fn case_changes_keep_every_character_of_the_mapping() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let title = glk.buffer_to_title_case_uni("\u{df}en", TitleCaseStyle::LowercaseRest);
+            assert!(title.starts_with("SS"));
+            assert_eq!("SSen", title);
+            assert_eq!(
+                "FIne",
+                glk.buffer_to_title_case_uni("\u{fb01}ne", TitleCaseStyle::UppercaseFirst)
+            );
+            assert_eq!("STRASSE", glk.buffer_to_upper_case_uni("stra\u{df}e"));
+
+            assert_eq!('\u{df}', glk.char_to_upper('\u{df}'));
+            assert_eq!('A', glk.char_to_upper('a'));
+            assert_eq!('\u{fc}', glk.char_to_lower('\u{dc}'));
+        });
+    }
+
     #[test]
     fn can_convert_char_to_keycode() {
         assert_eq!(Keycode::Basic('c'), 'c'.into());
     }
 
+    #[test]
+    fn can_approximate_ligatures() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert_eq!(
+                GestaltResult::CharOutput(OutputType::ApproxPrint(2)),
+                glk.gestalt(Gestalt::CharOutput(Keycode::Basic('œ')))
+            );
+            assert_eq!(
+                GestaltResult::CharOutput(OutputType::ApproxPrint(2)),
+                glk.gestalt(Gestalt::CharOutput(Keycode::Basic('—')))
+            );
+        });
+    }
+
+    #[test]
+    fn cannot_print_control_characters() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            assert_eq!(
+                GestaltResult::CharOutput(OutputType::CannotPrint(1)),
+                glk.gestalt(Gestalt::CharOutput(Keycode::Basic('\u{7}')))
+            );
+            assert_eq!(
+                GestaltResult::CharOutput(OutputType::ExactPrint),
+                glk.gestalt(Gestalt::CharOutput(Keycode::Basic('f')))
+            );
+        });
+    }
+
+    #[test]
+    fn can_write_to_a_window_echo_stream() {
+        Glk::<GlkTestWindow>::start(|glk| {
+            let win = glk
+                .window_open(None, GlkWindowType::TextBuffer, None, 73)
+                .unwrap();
+            assert!(glk.window_get_echo_stream(win).is_none());
+
+            let win_stream = glk.window_get_stream(win).unwrap();
+
+            let mem_stream = glk.stream_open_memory(vec![0u8; 20], GlkFileMode::Write, 74);
+
+            glk.window_set_echo_stream(win, Some(mem_stream));
+            let echo_stream = glk.window_get_echo_stream(win);
+            assert_eq!(Some(mem_stream), echo_stream);
+
+            glk.put_buffer_stream(win_stream, b"hello, world!");
+
+            // this should detach the echo stream from the window automatically
+            let close = glk.stream_close(mem_stream);
+            assert!(close.is_some());
+            if let Some((result, StreamCloseData::Bytes(bytes))) = close {
+                assert_eq!(result.read_count, 0);
+                assert_eq!(result.write_count, 13);
+                assert_eq!(&bytes[0..13], b"hello, world!");
+            } else {
+                panic!("stream_close() did not return valid results");
+            }
+
+            let echo_stream = glk.window_get_echo_stream(win);
+            assert!(echo_stream.is_none());
+        });
+    }
+
     /*
     #[test]
     fn can_handle_characters() {
@@ -417,51 +619,5 @@ mod test {
         assert!(glk.stream_get_current().is_none());
     }
 
-    #[test]
-    fn can_write_to_a_window_echo_stream() {
-        let mut glk = Glk::<GlkTestWindow>::new();
-
-        let win = glk
-            .window_open(None, GlkWindowType::TextBuffer, None, 73)
-            .unwrap();
-        assert!(glk.window_get_echo_stream(win).is_none());
-
-        let win_stream = glk.window_get_stream(win).unwrap();
-
-        let mem_stream = glk.stream_open_memory(vec![0u8; 20], GlkFileMode::Write, 74);
-
-        glk.window_set_echo_stream(win, Some(mem_stream));
-        let echo_stream = glk.window_get_echo_stream(win);
-        assert_eq!(Some(mem_stream), echo_stream);
-
-        glk.put_buffer_stream(
-            win_stream,
-            "hello, world!"
-                .chars()
-                .map(|ch| ch as u8)
-                .collect::<Vec<_>>()
-                .as_slice(),
-        );
-
-        // this should detach the echo stream from the window automatically
-        let close = glk.stream_close(mem_stream);
-        assert!(close.is_some());
-        if let Some((result, Some(bytes))) = close {
-            assert_eq!(result.read_count, 0);
-            assert_eq!(result.write_count, 13);
-            assert_eq!(
-                bytes[0..13],
-                "hello, world!"
-                    .chars()
-                    .map(|ch| ch as u8)
-                    .collect::<Vec<_>>()
-            );
-        } else {
-            panic!("stream_close() did not return valid results");
-        }
-
-        let echo_stream = glk.window_get_echo_stream(win);
-        assert!(echo_stream.is_none());
-    }
     */
 }
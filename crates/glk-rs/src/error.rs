@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use crate::windows::GlkWindowID;
+
+/// Errors reported by the glk library
+#[derive(Error, Debug, PartialEq)]
+pub enum GlkError {
+    /// The window ID does not refer to an open window
+    #[error("No such window {0}")]
+    InvalidWindow(GlkWindowID),
+
+    /// Windows of the requested type cannot be opened through the API
+    #[error("Cannot open a window of this type")]
+    InvalidWindowType,
+
+    /// The split amount is out of range (proportional splits must be 0 to 100 percent)
+    #[error("Invalid split amount")]
+    InvalidSplitAmount,
+}
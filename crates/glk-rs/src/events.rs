@@ -18,6 +18,110 @@ pub enum LineInput {
     Unicode(Vec<u32>),
 }
 
+/// The kind of keystroke a window's character input request will accept
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum CharRequest {
+    /// Latin-1 characters only
+    Latin1,
+    /// Any unicode character
+    Unicode,
+}
+
+/// The kinds of input a window can be waiting for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RequestKind {
+    /// A line of text
+    Line,
+    /// A single keystroke
+    Char,
+    /// A mouse click
+    Mouse,
+    /// A selected hyperlink
+    Hyperlink,
+}
+
+/// How a window should handle its line input requests
+#[derive(Clone, PartialEq, Debug)]
+pub struct LineInputSettings {
+    /// whether the line is shown in the window once it has been entered
+    pub echo: bool,
+    /// the keys which end the line as well as Return
+    pub terminators: Vec<Keycode>,
+}
+
+impl Default for LineInputSettings {
+    fn default() -> Self {
+        Self {
+            echo: true,
+            terminators: Vec::new(),
+        }
+    }
+}
+
+/// Input which was delivered to the game, one line of an input record file each
+#[derive(PartialEq, Debug)]
+pub(crate) enum RecordedInput {
+    /// A line of input
+    Line(String),
+    /// A single keystroke
+    Key(Keycode),
+}
+
+impl RecordedInput {
+    pub(crate) fn from_event(event: &GlkEvent) -> Option<Self> {
+        match event {
+            GlkEvent::LineInput {
+                buf: LineInput::Latin1(buf),
+                ..
+            } => Some(Self::Line(buf.iter().map(|&ch| ch as char).collect())),
+            GlkEvent::LineInput {
+                buf: LineInput::Unicode(buf),
+                ..
+            } => Some(Self::Line(
+                buf.iter().filter_map(|&ch| char::from_u32(ch)).collect(),
+            )),
+            GlkEvent::CharInput { key, .. } => Some(Self::Key(*key)),
+            _ => None,
+        }
+    }
+
+    // line breaks are escaped so that every input takes up exactly one line of the file
+    pub(crate) fn to_record(&self) -> String {
+        match self {
+            Self::Line(line) => format!("line:{}", line.replace('\\', "\\\\").replace('\n', "\\n")),
+            Self::Key(Keycode::Basic(ch)) => format!("char:{ch}"),
+            Self::Key(key) => format!("key:{key:?}"),
+        }
+    }
+
+    pub(crate) fn from_record(record: &str) -> Option<Self> {
+        if let Some(line) = record.strip_prefix("line:") {
+            let mut result = String::new();
+            let mut chars = line.chars();
+            while let Some(ch) = chars.next() {
+                match ch {
+                    '\\' => match chars.next()? {
+                        'n' => result.push('\n'),
+                        ch => result.push(ch),
+                    },
+                    ch => result.push(ch),
+                }
+            }
+            return Some(Self::Line(result));
+        }
+
+        if let Some(ch) = record.strip_prefix("char:") {
+            return Some(Self::Key(Keycode::Basic(ch.chars().next()?)));
+        }
+
+        let name = record.strip_prefix("key:")?;
+        Keycode::SPECIAL_KEYS
+            .into_iter()
+            .find(|key| format!("{key:?}") == name)
+            .map(Self::Key)
+    }
+}
+
 /// Events
 #[derive(PartialEq, Debug)]
 pub enum GlkEvent {
@@ -42,6 +146,12 @@ pub enum GlkEvent {
 
         /// The line that was read
         buf: LineInput,
+
+        /// How many characters were entered
+        len: usize,
+
+        /// The key which ended the line, or None if it was ended with the Enter key
+        terminator: Option<Keycode>,
     },
 
     /// A mouse event from a text grid or graphics window
@@ -168,6 +278,22 @@ impl EventManager {
         }
     }
 
+    // This will wait until the deadline for an event to arrive. If none arrives in
+    // time, then return GlkEvent::None
+    pub(crate) fn wait_for_event(&mut self, deadline: Instant) -> GlkEvent {
+        let event = self.pop_event();
+        if event != GlkEvent::None {
+            return event;
+        }
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        self.rx.recv_timeout(timeout).unwrap_or(GlkEvent::None)
+    }
+
+    pub(crate) fn post_event(&mut self, event: GlkEvent) {
+        self.pending.push_back(event);
+    }
+
     pub(crate) fn set_timer(&mut self, ms: u32) {
         self.timer_interval = Duration::from_millis(ms as u64);
     }
@@ -177,9 +303,28 @@ impl EventManager {
         winref: &WindowRef<T>,
         buf: &[u8],
         initlen: usize,
+        settings: &LineInputSettings,
     ) {
         let input = LineInput::Latin1(Vec::from(buf));
-        winref.get_line(input, initlen, self.tx.clone());
+        winref.get_line(input, initlen, settings, self.tx.clone());
+    }
+
+    pub(crate) fn queue_char_input_request<T: GlkWindow + Default>(
+        &mut self,
+        winref: &WindowRef<T>,
+    ) {
+        winref.get_char(self.tx.clone());
+    }
+
+    pub(crate) fn queue_mouse_request<T: GlkWindow + Default>(&mut self, winref: &WindowRef<T>) {
+        winref.request_mouse(self.tx.clone());
+    }
+
+    pub(crate) fn queue_hyperlink_request<T: GlkWindow + Default>(
+        &mut self,
+        winref: &WindowRef<T>,
+    ) {
+        winref.request_hyperlink(self.tx.clone());
     }
 
     pub(crate) fn queue_line_input_uni_request<T: GlkWindow + Default>(
@@ -187,9 +332,10 @@ impl EventManager {
         winref: &WindowRef<T>,
         buf: &[u32],
         initlen: usize,
+        settings: &LineInputSettings,
     ) {
         let input = LineInput::Unicode(Vec::from(buf));
-        winref.get_line(input, initlen, self.tx.clone());
+        winref.get_line(input, initlen, settings, self.tx.clone());
     }
 }
 
@@ -202,4 +348,19 @@ mod test {
         let foo = GlkEvent::None;
         assert_eq!(foo, GlkEvent::None);
     }
+
+    #[test]
+    fn recorded_input_survives_a_round_trip() {
+        for input in [
+            RecordedInput::Line("open the door".to_string()),
+            RecordedInput::Line("back\\slash and\nnewline".to_string()),
+            RecordedInput::Key(Keycode::Basic('é')),
+            RecordedInput::Key(Keycode::Func3),
+        ] {
+            let record = input.to_record();
+            assert!(!record.contains('\n'));
+            assert_eq!(RecordedInput::from_record(&record), Some(input));
+        }
+        assert_eq!(RecordedInput::from_record("key:Banana"), None);
+    }
 }
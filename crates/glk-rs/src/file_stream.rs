@@ -2,7 +2,7 @@ use mktemp::Temp;
 use std::{
     collections::HashMap,
     fs::{File, OpenOptions},
-    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
     path::PathBuf,
 };
 
@@ -15,9 +15,12 @@ use crate::{
 /// A reference to a file
 pub type GlkFileRef = u32;
 
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub(crate) struct FileRefManager {
     fileref: HashMap<GlkFileRef, FileRef>,
+    // temp files are removed when their guard is dropped, so keep it for as long as the
+    // fileref could still be used
+    temp_files: HashMap<GlkFileRef, Temp>,
     val: GlkFileRef,
 }
 
@@ -31,7 +34,10 @@ impl FileRefManager {
         usage: GlkFileUsage,
         rock: GlkRock,
     ) -> Option<GlkFileRef> {
-        self.create_file(usage, Temp::new_file().unwrap().to_path_buf(), rock, true)
+        let temp = Temp::new_file().ok()?;
+        let id = self.create_file(usage, temp.to_path_buf(), rock, true)?;
+        self.temp_files.insert(id, temp);
+        Some(id)
     }
 
     pub(crate) fn create_named_file(
@@ -53,7 +59,7 @@ impl FileRefManager {
         self.fileref.insert(
             self.val,
             FileRef {
-                _usage: usage,
+                usage,
                 name,
                 _rock: rock,
                 is_temp,
@@ -66,6 +72,7 @@ impl FileRefManager {
     }
 
     pub(crate) fn delete_file_by_id(&mut self, id: GlkFileRef) {
+        self.temp_files.remove(&id);
         if let Some(file) = self.fileref.get(&id) {
             let _ = std::fs::remove_file(&file.name);
         }
@@ -76,10 +83,10 @@ impl FileRefManager {
 #[derive(Clone, Debug)]
 pub(crate) struct FileRef {
     /// The usage of the file
-    _usage: GlkFileUsage,
+    usage: GlkFileUsage,
 
     /// The name of the file
-    name: PathBuf,
+    pub(crate) name: PathBuf,
 
     /// The file reference rock
     _rock: GlkRock,
@@ -88,7 +95,15 @@ pub(crate) struct FileRef {
     pub(crate) is_temp: bool,
 }
 
-impl FileRef {}
+impl FileRef {
+    // files which hold text, as opposed to binary data
+    fn is_text_mode(&self) -> bool {
+        matches!(
+            self.usage,
+            GlkFileUsage::TextMode | GlkFileUsage::Transcript | GlkFileUsage::InputRecord
+        )
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct FileStream {
@@ -96,6 +111,11 @@ pub(crate) struct FileStream {
     _rock: GlkRock,
     fp: Option<File>,
     input_buf: Option<BufReader<File>>,
+    bom_checked: bool,
+    /// opened with stream_open_file_uni
+    pub(crate) unicode: bool,
+    /// the last IO error, which has not been reported yet
+    last_error: Option<io::Error>,
 }
 
 impl FileStream {
@@ -112,6 +132,9 @@ impl FileStream {
             _rock: rock,
             fp: Some(fp),
             input_buf: None,
+            bom_checked: false,
+            unicode: false,
+            last_error: None,
         })
     }
 
@@ -124,16 +147,96 @@ impl FileStream {
             .create(mode != GlkFileMode::Read)
             .truncate(mode == GlkFileMode::Write);
 
-        let fp = options.open(fileref.name.clone()).ok()?;
+        let mut fp = options.open(fileref.name.clone()).ok()?;
+        if mode == GlkFileMode::WriteAppend {
+            // appended writes always go to the end, but the position starts at the
+            // beginning of the file until something is written
+            fp.seek(SeekFrom::End(0)).ok()?;
+        }
 
         Some(Self {
             _fileref: fileref.clone(),
             _rock: rock,
             fp: Some(fp),
             input_buf: None,
+            bom_checked: false,
+            unicode: false,
+            last_error: None,
         })
     }
 
+    // remember an IO failure, so that it can be reported later. running out of data
+    // before a read is complete is not a failure
+    fn check<V>(&mut self, result: io::Result<V>) -> Option<V> {
+        match result {
+            Ok(val) => Some(val),
+            Err(err) => {
+                if err.kind() != ErrorKind::UnexpectedEof {
+                    self.last_error = Some(err);
+                }
+                None
+            }
+        }
+    }
+
+    // unicode streams on binary files store every character as a four-byte big-endian value
+    fn is_four_byte(&self) -> bool {
+        self.unicode && !self._fileref.is_text_mode()
+    }
+
+    // text files may start with a UTF-8 byte order mark, which is not part of the text.
+    // every unicode reader goes through get_char_uni(), which skips it
+    fn skip_bom(&mut self) {
+        if self.bom_checked {
+            return;
+        }
+        self.bom_checked = true;
+        if !self._fileref.is_text_mode() {
+            return;
+        }
+
+        let br = self.get_bufreader();
+        if br.stream_position().ok() == Some(0)
+            && br
+                .fill_buf()
+                .is_ok_and(|buf| buf.starts_with(b"\xef\xbb\xbf"))
+        {
+            br.consume(3);
+        }
+    }
+
+    // read characters one at a time, in whichever encoding get_char_uni() uses, until
+    // maxlen characters have been read or the line ends. like get_line(), a line keeps
+    // its line break
+    fn get_chars(&mut self, maxlen: Option<usize>, end_char: Option<char>) -> String {
+        let mut result = String::new();
+        let mut count = 0;
+        while maxlen.is_none_or(|max| count < max) {
+            let Some(ch) = self.get_char_uni() else {
+                break;
+            };
+            result.push(ch);
+            count += 1;
+            if Some(ch) == end_char {
+                break;
+            }
+        }
+        result
+    }
+
+    // the reader shares its file offset with fp, but runs ahead of it by whatever it has
+    // buffered. move the offset back to where reading got to, and drop the stale buffer,
+    // before anything uses fp directly
+    fn sync_reader(&mut self) {
+        let (Some(br), Some(fp)) = (self.input_buf.take(), self.fp.as_mut()) else {
+            return;
+        };
+        let buffered = br.buffer().len() as i64;
+        if buffered > 0 {
+            let _ = fp.seek(SeekFrom::Current(-buffered));
+        }
+    }
+
     fn get_bufreader(&mut self) -> &mut BufReader<File> {
         if self.input_buf.is_none() {
             self.input_buf = Some(BufReader::new(
@@ -159,8 +262,14 @@ impl GlkStreamHandler for FileStream {
     }
 
     fn put_char(&mut self, ch: u8) -> WriteResponse {
+        if self.unicode {
+            return self.put_char_uni(ch as char);
+        }
+
+        self.sync_reader();
         if let Some(fp) = self.fp.as_mut() {
-            if write!(fp, "{ch}").is_ok() {
+            let result = fp.write_all(&[ch]);
+            if self.check(result).is_some() {
                 return WriteResponse::quick(1);
             }
         }
@@ -187,9 +296,16 @@ impl GlkStreamHandler for FileStream {
     }
 
     fn put_char_uni(&mut self, ch: char) -> WriteResponse {
+        let bytestream = if self.is_four_byte() {
+            (ch as u32).to_be_bytes().to_vec()
+        } else {
+            GlkStream::char_to_bytestream(ch)
+        };
+
+        self.sync_reader();
         if let Some(fp) = self.fp.as_mut() {
-            let bytestream = GlkStream::char_to_bytestream(ch);
-            if fp.write(bytestream.as_slice()).is_ok() {
+            let result = fp.write(bytestream.as_slice());
+            if self.check(result).is_some() {
                 return WriteResponse::quick(bytestream.len());
             }
         }
@@ -206,72 +322,83 @@ impl GlkStreamHandler for FileStream {
     }
 
     fn get_char(&mut self) -> Option<u8> {
-        let br = self.get_bufreader();
         let mut buf = [0u8];
-        if br.read(&mut buf).is_ok() {
-            Some(buf[0])
-        } else {
-            None
-        }
+        let result = self.get_bufreader().read(&mut buf);
+        self.check(result)?;
+        Some(buf[0])
     }
 
     fn get_buffer(&mut self, maxlen: Option<usize>) -> Vec<u8> {
+        self.sync_reader();
         let Some(mut fp) = self.fp.as_ref() else {
             return Vec::new();
         };
 
-        if let Some(maxlen) = maxlen {
-            let mut buf = vec![0u8; maxlen];
-            let _ = fp.read(&mut buf);
-            buf
+        // only return what was actually read, so the stream's read count stays accurate
+        let mut buf: Vec<u8> = Vec::new();
+        let result = if let Some(maxlen) = maxlen {
+            fp.take(maxlen as u64).read_to_end(&mut buf)
         } else {
-            let mut buf: Vec<u8> = Vec::new();
-            let _ = fp.read_to_end(&mut buf);
-            buf
-        }
+            fp.read_to_end(&mut buf)
+        };
+        self.check(result);
+        buf
     }
 
     fn get_line(&mut self, maxlen: Option<usize>) -> Vec<u8> {
-        let mut result = String::from("");
-
         let br = self.get_bufreader();
 
-        let _ = if let Some(maxlen) = maxlen {
-            let mut buf = vec![0u8; maxlen];
-
-            if br.read_exact(&mut buf).is_err() {
-                return Vec::new();
-            };
-
-            if let Some(pos) = buf.iter().position(|x| *x == b'\n') {
-                let seek_to = (maxlen - pos) as i64 - 1;
-                let _ = br.seek_relative(-seek_to);
-                return buf.into_iter().take(pos + 1).collect::<Vec<u8>>();
-            }
-
-            result = buf.into_iter().map(|x| x as char).collect::<String>();
-            Ok(result.len())
+        // keep whatever was read before an error, which is recorded separately
+        let mut buf = Vec::new();
+        let result = if let Some(maxlen) = maxlen {
+            br.by_ref().take(maxlen as u64).read_until(b'\n', &mut buf)
         } else {
-            br.read_line(&mut result)
+            br.read_until(b'\n', &mut buf)
         };
-
-        result.chars().map(|x| x as u8).collect()
+        self.check(result);
+        buf
     }
 
     fn get_char_uni(&mut self) -> Option<char> {
+        if self.is_four_byte() {
+            let mut buf = [0u8; 4];
+            let result = self.get_bufreader().read_exact(&mut buf);
+            self.check(result)?;
+            return char::from_u32(u32::from_be_bytes(buf));
+        }
+
+        self.skip_bom();
         GlkStream::bytestream_to_char(self.get_bufreader())
     }
 
-    fn get_buffer_uni(&mut self, _maxlen: Option<usize>) -> String {
-        todo!()
+    fn get_buffer_uni(&mut self, maxlen: Option<usize>) -> String {
+        self.get_chars(maxlen, None)
     }
 
-    fn get_line_uni(&mut self, _maxlen: Option<usize>) -> String {
-        todo!()
+    fn get_line_uni(&mut self, maxlen: Option<usize>) -> String {
+        self.get_chars(maxlen, Some('\n'))
+    }
+
+    fn reads_unicode(&self) -> bool {
+        self.unicode || self._fileref.is_text_mode()
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.last_error.take()
     }
 
     fn get_position(&self) -> u32 {
-        todo!()
+        // file positions are byte offsets, even after reading unicode characters. the
+        // file's own offset is ahead of the reader by however much it has buffered
+        let Some(mut fp) = self.fp.as_ref() else {
+            return 0;
+        };
+        let offset = fp.stream_position().unwrap_or(0);
+        let buffered = self
+            .input_buf
+            .as_ref()
+            .map_or(0, |br| br.buffer().len() as u64);
+        offset.saturating_sub(buffered) as u32
     }
 
     fn set_position(&mut self, pos: i32, seekmode: crate::GlkSeekMode) -> Option<()> {
@@ -281,6 +408,7 @@ impl GlkStreamHandler for FileStream {
             crate::GlkSeekMode::End if pos <= 0 => SeekFrom::End(pos as i64),
             _ => return None,
         };
+        self.sync_reader();
         if let Some(fp) = self.fp.as_mut() {
             fp.seek(seek_to).ok()?;
         }
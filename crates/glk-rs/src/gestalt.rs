@@ -69,6 +69,12 @@ pub enum Gestalt {
 
     /// Can we open and read resources streams
     ResourceStream,
+
+    /// Can the library display hyperlinks
+    Hyperlinks,
+
+    /// Can the library report when a hyperlink is selected
+    HyperlinkInput,
 }
 
 /// The responses for different gestalt queries
@@ -84,6 +90,41 @@ pub enum GestaltResult {
     CharOutput(OutputType),
 }
 
+// Characters outside the printable range which can be approximated with plain ascii
+const TRANSLITERATIONS: &[(char, &str)] = &[
+    ('œ', "oe"),
+    ('Œ', "OE"),
+    ('æ', "ae"),
+    ('Æ', "AE"),
+    ('ß', "ss"),
+    ('ĳ', "ij"),
+    ('Ĳ', "IJ"),
+    ('ﬁ', "fi"),
+    ('ﬂ', "fl"),
+    ('—', "--"),
+    ('–', "-"),
+    ('‘', "'"),
+    ('’', "'"),
+    ('“', "\""),
+    ('”', "\""),
+    ('…', "..."),
+    ('«', "<<"),
+    ('»', ">>"),
+    ('©', "(c)"),
+    ('®', "(R)"),
+    ('™', "(TM)"),
+    ('×', "x"),
+    ('\u{a0}', " "),
+];
+
+/// returns the plain ascii approximation of a character, if there is one
+pub(crate) fn transliterate(ch: char) -> Option<&'static str> {
+    TRANSLITERATIONS
+        .iter()
+        .find(|(from, _)| *from == ch)
+        .map(|(_, to)| *to)
+}
+
 /// The way a given character will be represented on screen
 #[derive(PartialEq, Debug)]
 pub enum OutputType {
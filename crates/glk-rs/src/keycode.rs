@@ -1,5 +1,5 @@
 /// Keycode translated from the i/o input system into GLK codes
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Keycode {
     /// A "normal" letter
     Basic(char),
@@ -55,15 +55,205 @@ pub enum Keycode {
     Unknown,
 }
 
+impl Keycode {
+    /// every key which is not a character
+    pub(crate) const SPECIAL_KEYS: [Keycode; 25] = [
+        Keycode::Left,
+        Keycode::Right,
+        Keycode::Up,
+        Keycode::Down,
+        Keycode::Return,
+        Keycode::Delete,
+        Keycode::Escape,
+        Keycode::Tab,
+        Keycode::PageUp,
+        Keycode::PageDown,
+        Keycode::Home,
+        Keycode::End,
+        Keycode::Func1,
+        Keycode::Func2,
+        Keycode::Func3,
+        Keycode::Func4,
+        Keycode::Func5,
+        Keycode::Func6,
+        Keycode::Func7,
+        Keycode::Func8,
+        Keycode::Func9,
+        Keycode::Func10,
+        Keycode::Func11,
+        Keycode::Func12,
+        Keycode::Unknown,
+    ];
+
+    /// convert a glk numeric keycode, either a special key constant or a character's
+    /// codepoint
+    pub fn from_u32(val: u32) -> Keycode {
+        match val {
+            0xfffffffe => Keycode::Left,
+            0xfffffffd => Keycode::Right,
+            0xfffffffc => Keycode::Up,
+            0xfffffffb => Keycode::Down,
+            0xfffffffa => Keycode::Return,
+            0xfffffff9 => Keycode::Delete,
+            0xfffffff8 => Keycode::Escape,
+            0xfffffff7 => Keycode::Tab,
+            0xfffffff6 => Keycode::PageUp,
+            0xfffffff5 => Keycode::PageDown,
+            0xfffffff4 => Keycode::Home,
+            0xfffffff3 => Keycode::End,
+            0xffffffef => Keycode::Func1,
+            0xffffffee => Keycode::Func2,
+            0xffffffed => Keycode::Func3,
+            0xffffffec => Keycode::Func4,
+            0xffffffeb => Keycode::Func5,
+            0xffffffea => Keycode::Func6,
+            0xffffffe9 => Keycode::Func7,
+            0xffffffe8 => Keycode::Func8,
+            0xffffffe7 => Keycode::Func9,
+            0xffffffe6 => Keycode::Func10,
+            0xffffffe5 => Keycode::Func11,
+            0xffffffe4 => Keycode::Func12,
+            _ => char::from_u32(val).map_or(Keycode::Unknown, Keycode::Basic),
+        }
+    }
+
+    /// can this key end line input, as well as Return? Keys which type a character can't
+    pub fn is_line_terminator(&self) -> bool {
+        !matches!(self, Keycode::Basic(_) | Keycode::Return | Keycode::Unknown)
+    }
+
+    /// translate an ANSI/VT escape sequence from a terminal, such as "\x1b[A", into the key
+    /// it stands for. modifier parameters (e.g. "\x1b[1;5A" for ctrl-up) are ignored
+    pub fn from_escape_sequence(seq: &str) -> Keycode {
+        if let Some(rest) = seq.strip_prefix("\x1bO") {
+            // SS3 sequences, sent for the arrow keys in application mode and for F1-F4
+            return match rest {
+                "A" => Keycode::Up,
+                "B" => Keycode::Down,
+                "C" => Keycode::Right,
+                "D" => Keycode::Left,
+                "H" => Keycode::Home,
+                "F" => Keycode::End,
+                "P" => Keycode::Func1,
+                "Q" => Keycode::Func2,
+                "R" => Keycode::Func3,
+                "S" => Keycode::Func4,
+                _ => Keycode::Unknown,
+            };
+        }
+
+        let Some(rest) = seq.strip_prefix("\x1b[") else {
+            return Keycode::Unknown;
+        };
+
+        // the linux console sends F1-F5 as "\x1b[[A" to "\x1b[[E"
+        if let Some(key) = rest.strip_prefix('[') {
+            return match key {
+                "A" => Keycode::Func1,
+                "B" => Keycode::Func2,
+                "C" => Keycode::Func3,
+                "D" => Keycode::Func4,
+                "E" => Keycode::Func5,
+                _ => Keycode::Unknown,
+            };
+        }
+
+        let Some(last) = rest.chars().last() else {
+            return Keycode::Unknown;
+        };
+        let params = &rest[..rest.len() - last.len_utf8()];
+        let mut params = params.split(';');
+        let first = params.next().unwrap_or_default();
+        if !params.all(|param| param.parse::<u32>().is_ok()) {
+            return Keycode::Unknown;
+        }
+
+        if last == '~' {
+            return match first.parse::<u32>() {
+                Ok(1 | 7) => Keycode::Home,
+                Ok(3) => Keycode::Delete,
+                Ok(4 | 8) => Keycode::End,
+                Ok(5) => Keycode::PageUp,
+                Ok(6) => Keycode::PageDown,
+                Ok(11) => Keycode::Func1,
+                Ok(12) => Keycode::Func2,
+                Ok(13) => Keycode::Func3,
+                Ok(14) => Keycode::Func4,
+                Ok(15) => Keycode::Func5,
+                Ok(17) => Keycode::Func6,
+                Ok(18) => Keycode::Func7,
+                Ok(19) => Keycode::Func8,
+                Ok(20) => Keycode::Func9,
+                Ok(21) => Keycode::Func10,
+                Ok(23) => Keycode::Func11,
+                Ok(24) => Keycode::Func12,
+                _ => Keycode::Unknown,
+            };
+        }
+
+        if !(first.is_empty() || first == "1") {
+            return Keycode::Unknown;
+        }
+        match last {
+            'A' => Keycode::Up,
+            'B' => Keycode::Down,
+            'C' => Keycode::Right,
+            'D' => Keycode::Left,
+            'H' => Keycode::Home,
+            'F' => Keycode::End,
+            'P' => Keycode::Func1,
+            'Q' => Keycode::Func2,
+            'R' => Keycode::Func3,
+            'S' => Keycode::Func4,
+            _ => Keycode::Unknown,
+        }
+    }
+}
+
+impl From<Keycode> for u32 {
+    fn from(key: Keycode) -> Self {
+        match key {
+            Keycode::Basic(ch) => ch as u32,
+            Keycode::Unknown => 0xffffffff,
+            Keycode::Left => 0xfffffffe,
+            Keycode::Right => 0xfffffffd,
+            Keycode::Up => 0xfffffffc,
+            Keycode::Down => 0xfffffffb,
+            Keycode::Return => 0xfffffffa,
+            Keycode::Delete => 0xfffffff9,
+            Keycode::Escape => 0xfffffff8,
+            Keycode::Tab => 0xfffffff7,
+            Keycode::PageUp => 0xfffffff6,
+            Keycode::PageDown => 0xfffffff5,
+            Keycode::Home => 0xfffffff4,
+            Keycode::End => 0xfffffff3,
+            Keycode::Func1 => 0xffffffef,
+            Keycode::Func2 => 0xffffffee,
+            Keycode::Func3 => 0xffffffed,
+            Keycode::Func4 => 0xffffffec,
+            Keycode::Func5 => 0xffffffeb,
+            Keycode::Func6 => 0xffffffea,
+            Keycode::Func7 => 0xffffffe9,
+            Keycode::Func8 => 0xffffffe8,
+            Keycode::Func9 => 0xffffffe7,
+            Keycode::Func10 => 0xffffffe6,
+            Keycode::Func11 => 0xffffffe5,
+            Keycode::Func12 => 0xffffffe4,
+        }
+    }
+}
+
 impl From<char> for Keycode {
     fn from(ch: char) -> Self {
         if (ch as u32) >= 32 && (ch as u32) < 127 {
             return Keycode::Basic(ch);
         }
-        if ch == '\r' || ch == '\n' {
-            Keycode::Return
-        } else {
-            Keycode::Unknown
+        match ch {
+            '\r' | '\n' => Keycode::Return,
+            '\t' => Keycode::Tab,
+            '\u{8}' | '\u{7f}' => Keycode::Delete,
+            '\u{1b}' => Keycode::Escape,
+            _ => Keycode::Unknown,
         }
     }
 }
@@ -77,4 +267,114 @@ mod test {
         assert_eq!(Keycode::Return, '\r'.into());
         assert_eq!(Keycode::Return, '\n'.into());
     }
+
+    #[test]
+    fn can_parse_arrow_key_sequences() {
+        assert_eq!(Keycode::Up, Keycode::from_escape_sequence("\x1b[A"));
+        assert_eq!(Keycode::Down, Keycode::from_escape_sequence("\x1b[B"));
+        assert_eq!(Keycode::Right, Keycode::from_escape_sequence("\x1b[C"));
+        assert_eq!(Keycode::Left, Keycode::from_escape_sequence("\x1b[D"));
+        assert_eq!(Keycode::Up, Keycode::from_escape_sequence("\x1bOA"));
+        assert_eq!(Keycode::Left, Keycode::from_escape_sequence("\x1bOD"));
+        assert_eq!(Keycode::Up, Keycode::from_escape_sequence("\x1b[1;5A"));
+    }
+
+    #[test]
+    fn can_parse_navigation_key_sequences() {
+        assert_eq!(Keycode::Home, Keycode::from_escape_sequence("\x1b[H"));
+        assert_eq!(Keycode::End, Keycode::from_escape_sequence("\x1b[F"));
+        assert_eq!(Keycode::Home, Keycode::from_escape_sequence("\x1bOH"));
+        assert_eq!(Keycode::End, Keycode::from_escape_sequence("\x1bOF"));
+        assert_eq!(Keycode::Home, Keycode::from_escape_sequence("\x1b[1~"));
+        assert_eq!(Keycode::End, Keycode::from_escape_sequence("\x1b[4~"));
+        assert_eq!(Keycode::Home, Keycode::from_escape_sequence("\x1b[7~"));
+        assert_eq!(Keycode::End, Keycode::from_escape_sequence("\x1b[8~"));
+        assert_eq!(Keycode::Delete, Keycode::from_escape_sequence("\x1b[3~"));
+        assert_eq!(Keycode::PageUp, Keycode::from_escape_sequence("\x1b[5~"));
+        assert_eq!(Keycode::PageDown, Keycode::from_escape_sequence("\x1b[6~"));
+        assert_eq!(
+            Keycode::PageDown,
+            Keycode::from_escape_sequence("\x1b[6;2~")
+        );
+    }
+
+    #[test]
+    fn can_parse_function_key_sequences() {
+        let keys = [
+            ("\x1bOP", Keycode::Func1),
+            ("\x1bOQ", Keycode::Func2),
+            ("\x1bOR", Keycode::Func3),
+            ("\x1bOS", Keycode::Func4),
+            ("\x1b[11~", Keycode::Func1),
+            ("\x1b[12~", Keycode::Func2),
+            ("\x1b[13~", Keycode::Func3),
+            ("\x1b[14~", Keycode::Func4),
+            ("\x1b[15~", Keycode::Func5),
+            ("\x1b[17~", Keycode::Func6),
+            ("\x1b[18~", Keycode::Func7),
+            ("\x1b[19~", Keycode::Func8),
+            ("\x1b[20~", Keycode::Func9),
+            ("\x1b[21~", Keycode::Func10),
+            ("\x1b[23~", Keycode::Func11),
+            ("\x1b[24~", Keycode::Func12),
+            ("\x1b[1;2P", Keycode::Func1),
+            ("\x1b[[A", Keycode::Func1),
+            ("\x1b[[E", Keycode::Func5),
+        ];
+        for (seq, key) in keys {
+            assert_eq!(key, Keycode::from_escape_sequence(seq), "{seq:?}");
+        }
+    }
+
+    #[test]
+    fn unrecognized_sequences_are_unknown() {
+        for seq in [
+            "",
+            "\x1b",
+            "\x1b[",
+            "\x1bOZ",
+            "\x1b[16~",
+            "\x1b[99~",
+            "\x1b[2A",
+            "\x1b[x;1A",
+            "[A",
+        ] {
+            assert_eq!(
+                Keycode::Unknown,
+                Keycode::from_escape_sequence(seq),
+                "{seq:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn special_keys_round_trip_through_glk_constants() {
+        for key in Keycode::SPECIAL_KEYS {
+            let val = u32::from(key);
+            assert!(val >= 0xffffffe4, "{key:?}");
+            assert_eq!(key, Keycode::from_u32(val));
+        }
+        assert_eq!(0xfffffffe, u32::from(Keycode::Left));
+        assert_eq!(0xfffffffa, u32::from(Keycode::Return));
+        assert_eq!(0xffffffe4, u32::from(Keycode::Func12));
+    }
+
+    #[test]
+    fn characters_convert_to_their_codepoints() {
+        assert_eq!(0x61, u32::from(Keycode::Basic('a')));
+        assert_eq!(0xe9, u32::from(Keycode::Basic('\u{e9}')));
+        assert_eq!(Keycode::Basic('a'), Keycode::from_u32(0x61));
+        assert_eq!(Keycode::Basic('\u{1f600}'), Keycode::from_u32(0x1f600));
+        assert_eq!(Keycode::Unknown, Keycode::from_u32(0xd800));
+        assert_eq!(Keycode::Unknown, Keycode::from_u32(0xfffffff0));
+    }
+
+    #[test]
+    fn can_convert_control_keys() {
+        assert_eq!(Keycode::Tab, '\t'.into());
+        assert_eq!(Keycode::Delete, '\u{8}'.into());
+        assert_eq!(Keycode::Delete, '\u{7f}'.into());
+        assert_eq!(Keycode::Escape, '\u{1b}'.into());
+        assert_eq!(Keycode::Unknown, '\u{7}'.into());
+    }
 }
@@ -1,16 +1,54 @@
 #![warn(missing_docs)]
 
 //! A rust library for writing glk-based code
+//!
+//! Implement [`GlkWindow`] to provide a back end for your platform, and then start
+//! a Glk session with it:
+//!
+//! ```
+//! use std::sync::mpsc::{Receiver, Sender};
+//!
+//! use rglk::{
+//!     Glk, GlkEvent, GlkMessage, GlkResult, GlkWindow, GlkWindowSize, GlkWindowType, LineInput,
+//! };
+//!
+//! #[derive(Default)]
+//! struct NullWindow;
+//!
+//! impl GlkWindow for NullWindow {
+//!     fn new(_request: Receiver<GlkMessage>, _result: Sender<GlkResult>) -> Self {
+//!         Self
+//!     }
+//!     fn run(&mut self) {}
+//!     fn get_size(&self) -> GlkWindowSize {
+//!         GlkWindowSize::default()
+//!     }
+//!     fn move_cursor(&mut self, _x: u32, _y: u32) {}
+//!     fn clear(&mut self) {}
+//!     fn get_line(&mut self, _event: LineInput, _initlen: usize, _tx: Sender<GlkEvent>) {}
+//! }
+//!
+//! Glk::<NullWindow>::start(|glk| {
+//!     let win = glk.window_open(None, GlkWindowType::TextBuffer, None, 0);
+//!     assert!(win.is_ok());
+//! });
+//! ```
 
 /// The main entry point for Glk
 pub mod entry;
 
+/// The error module
+pub mod error;
+
 /// The gestalt module
 pub mod gestalt;
 
 /// The keycode module
 pub mod keycode;
 
+/// The style module
+pub mod style;
+
 /// The windows module
 pub mod windows;
 
@@ -98,14 +136,24 @@ pub mod prelude {
 
     /// The main entry point for all things glk
     pub use crate::entry::Glk;
+    pub use crate::error::*;
     pub use crate::events::*;
     pub use crate::gestalt::*;
     pub use crate::keycode::*;
+    pub use crate::style::*;
     pub use crate::windows::*;
 }
 
+pub use entry::{Glk, GlkMessage, GlkResult};
+pub use error::GlkError;
+pub use events::{GlkEvent, LineInput, LineInputSettings, RequestKind};
+pub use stream::StreamCloseData;
+pub use windows::{GlkWindow, GlkWindowSize, GlkWindowType};
+
 use prelude::*;
 
 pub(crate) mod file_stream;
 pub(crate) mod mem_stream;
+pub(crate) mod resource_stream;
+pub(crate) mod schannel;
 pub(crate) mod stream;
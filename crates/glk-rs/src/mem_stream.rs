@@ -9,6 +9,8 @@ use crate::{
 pub(crate) struct MemStream {
     buf: Vec<u8>,
     cursor: RefCell<usize>,
+    // whether the last line read ended with a line break
+    line_break: bool,
 }
 
 impl MemStream {
@@ -28,9 +30,11 @@ impl MemStream {
         };
 
         let mut result = Vec::new();
+        self.line_break = false;
         for _ in 0..count {
             if let Some(ch) = self.get_char() {
                 if Some(ch) == end_char {
+                    self.line_break = true;
                     break;
                 }
                 result.push(ch);
@@ -49,9 +53,11 @@ impl MemStream {
         };
 
         let mut result = String::new();
+        self.line_break = false;
         for _ in 0..count {
             if let Some(ch) = self.get_char_uni() {
                 if Some(ch) == end_char {
+                    self.line_break = true;
                     break;
                 }
                 result.push(ch);
@@ -157,6 +163,10 @@ impl GlkStreamHandler for MemStream {
         *self.cursor.borrow() as u32
     }
 
+    fn dropped_line_break(&self) -> bool {
+        self.line_break
+    }
+
     fn set_position(&mut self, pos: i32, seekmode: crate::GlkSeekMode) -> Option<()> {
         let new_cursor = match seekmode {
             GlkSeekMode::Start => pos,
@@ -184,3 +194,155 @@ impl GlkStreamHandler for MemStream {
         true
     }
 }
+
+/// A memory stream over a buffer of unicode codepoints, one per character
+#[derive(Debug, Default)]
+pub(crate) struct MemStreamUni {
+    buf: Vec<u32>,
+    cursor: usize,
+    // whether the last line read ended with a line break
+    line_break: bool,
+}
+
+impl MemStreamUni {
+    pub(crate) fn new(buf: Vec<u32>) -> Self {
+        Self {
+            buf,
+            ..Self::default()
+        }
+    }
+
+    fn get_chars(&mut self, maxlen: Option<usize>, end_char: Option<char>) -> String {
+        let remaining = self.buf.len() - self.cursor;
+        let count = maxlen.map_or(remaining, |max| max.min(remaining));
+
+        let mut result = String::new();
+        self.line_break = false;
+        for _ in 0..count {
+            if let Some(ch) = self.get_char_uni() {
+                if Some(ch) == end_char {
+                    self.line_break = true;
+                    break;
+                }
+                result.push(ch);
+            }
+        }
+
+        result
+    }
+
+    // characters outside of Latin-1 are read as '?' by the byte functions
+    fn to_latin1(text: String) -> Vec<u8> {
+        text.chars()
+            .map(|ch| u8::try_from(ch).unwrap_or(b'?'))
+            .collect()
+    }
+}
+
+impl GlkStreamHandler for MemStreamUni {
+    fn get_echo_stream(&self) -> Option<GlkStreamID> {
+        None
+    }
+
+    fn close(&mut self) {}
+
+    fn put_char(&mut self, ch: u8) -> WriteResponse {
+        self.put_char_uni(ch as char)
+    }
+
+    fn put_char_uni(&mut self, ch: char) -> WriteResponse {
+        if self.cursor < self.buf.len() {
+            self.buf[self.cursor] = ch as u32;
+            self.cursor += 1;
+            WriteResponse::quick(1)
+        } else {
+            WriteResponse::quick(0)
+        }
+    }
+
+    fn put_string(&mut self, s: &str) -> WriteResponse {
+        WriteResponse::quick(s.chars().map(|ch| self.put_char_uni(ch).len).sum())
+    }
+
+    fn put_buffer(&mut self, buf: &[u8]) -> WriteResponse {
+        WriteResponse::quick(buf.iter().map(|byte| self.put_char(*byte).len).sum())
+    }
+
+    fn put_buffer_uni(&mut self, buf: &[char]) -> WriteResponse {
+        WriteResponse::quick(buf.iter().map(|ch| self.put_char_uni(*ch).len).sum())
+    }
+
+    fn get_char(&mut self) -> Option<u8> {
+        let ch = self.get_char_uni()?;
+        Some(u8::try_from(ch).unwrap_or(b'?'))
+    }
+
+    fn get_buffer(&mut self, maxlen: Option<usize>) -> Vec<u8> {
+        let text = self.get_chars(maxlen, None);
+        Self::to_latin1(text)
+    }
+
+    fn get_line(&mut self, maxlen: Option<usize>) -> Vec<u8> {
+        let text = self.get_chars(maxlen, Some('\n'));
+        Self::to_latin1(text)
+    }
+
+    fn get_char_uni(&mut self) -> Option<char> {
+        let val = *self.buf.get(self.cursor)?;
+        self.cursor += 1;
+        Some(char::from_u32(val).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    fn get_buffer_uni(&mut self, maxlen: Option<usize>) -> String {
+        self.get_chars(maxlen, None)
+    }
+
+    fn get_line_uni(&mut self, maxlen: Option<usize>) -> String {
+        self.get_chars(maxlen, Some('\n'))
+    }
+
+    // positions count characters rather than bytes
+    fn get_position(&self) -> u32 {
+        self.cursor as u32
+    }
+
+    fn dropped_line_break(&self) -> bool {
+        self.line_break
+    }
+
+    fn reads_unicode(&self) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, pos: i32, seekmode: GlkSeekMode) -> Option<()> {
+        let new_cursor = match seekmode {
+            GlkSeekMode::Start => pos,
+            GlkSeekMode::Current => self.cursor as i32 + pos,
+            GlkSeekMode::End => self.buf.len() as i32 + pos,
+        };
+
+        if new_cursor < 0 || new_cursor > self.buf.len() as i32 {
+            None
+        } else {
+            self.cursor = new_cursor as usize;
+            Some(())
+        }
+    }
+
+    // each codepoint as four big-endian bytes
+    fn get_data(&self) -> Vec<u8> {
+        self.buf.iter().flat_map(|val| val.to_be_bytes()).collect()
+    }
+
+    fn get_data_uni(&self) -> Vec<u32> {
+        self.buf.clone()
+    }
+
+    fn is_window_stream(&self) -> bool {
+        false
+    }
+
+    fn is_memory_stream(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,200 @@
+use std::rc::Rc;
+
+use blorb::{
+    types::{BlorbType, ResourceType},
+    BlorbReader,
+};
+
+use crate::{
+    stream::{GlkStreamHandler, GlkStreamID, WriteResponse},
+    GlkSeekMode,
+};
+
+/// A read-only stream over a Data resource which borrows its bytes from the
+/// blorb reader instead of keeping a copy of them
+pub(crate) struct ResourceStream {
+    blorb: Rc<BlorbReader>,
+    // where the resource's chunk is in the blorb file, and how long its data is, so it
+    // doesn't have to be looked up in the resource index on every read
+    offset: usize,
+    len: usize,
+    cursor: usize,
+    unicode: bool,
+    is_text: bool,
+    // whether the last line read ended with a line break
+    line_break: bool,
+}
+
+impl ResourceStream {
+    pub(crate) fn new(blorb: &Rc<BlorbReader>, id: usize, unicode: bool) -> Option<Self> {
+        let chunk = blorb.get_resource(ResourceType::Data, id).ok()?;
+        Some(Self {
+            blorb: Rc::clone(blorb),
+            offset: chunk.offset(),
+            len: chunk.bytes.len(),
+            cursor: 0,
+            unicode,
+            is_text: chunk.blorb_type == BlorbType::Text,
+            line_break: false,
+        })
+    }
+
+    pub(crate) fn bytes(&self) -> &[u8] {
+        self.blorb
+            .get_chunk_at(self.offset)
+            .map(|chunk| chunk.bytes)
+            .unwrap_or_default()
+    }
+
+    fn get_bytes(&mut self, maxlen: Option<usize>, end_char: Option<u8>) -> Vec<u8> {
+        let remaining_bytes = self.len - self.cursor;
+        let count = maxlen.map_or(remaining_bytes, |max| max.min(remaining_bytes));
+
+        let mut result = Vec::new();
+        self.line_break = false;
+        for _ in 0..count {
+            if let Some(ch) = self.get_char() {
+                if Some(ch) == end_char {
+                    self.line_break = true;
+                    break;
+                }
+                result.push(ch);
+            }
+        }
+
+        result
+    }
+
+    fn get_uni(&mut self, maxlen: Option<usize>, end_char: Option<char>) -> String {
+        let mut result = String::new();
+        self.line_break = false;
+        for _ in 0..maxlen.unwrap_or(usize::MAX) {
+            match self.get_char_uni() {
+                Some(ch) if Some(ch) != end_char => result.push(ch),
+                Some(_) => {
+                    self.line_break = true;
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        result
+    }
+
+    // invalid UTF-8 is skipped and read as U+FFFD, so the text after it can still be read
+    fn get_utf8_char(&mut self) -> Option<char> {
+        let chunk = self.bytes()[self.cursor..].utf8_chunks().next()?;
+        let (ch, width) = match chunk.valid().chars().next() {
+            Some(ch) => (ch, ch.len_utf8()),
+            None => (char::REPLACEMENT_CHARACTER, chunk.invalid().len()),
+        };
+        self.cursor += width;
+        Some(ch)
+    }
+}
+
+impl GlkStreamHandler for ResourceStream {
+    fn get_echo_stream(&self) -> Option<GlkStreamID> {
+        None
+    }
+
+    fn close(&mut self) {}
+
+    fn put_char(&mut self, _ch: u8) -> WriteResponse {
+        WriteResponse::quick(0)
+    }
+
+    fn put_char_uni(&mut self, _ch: char) -> WriteResponse {
+        WriteResponse::quick(0)
+    }
+
+    fn put_string(&mut self, _s: &str) -> WriteResponse {
+        WriteResponse::quick(0)
+    }
+
+    fn put_buffer(&mut self, _buf: &[u8]) -> WriteResponse {
+        WriteResponse::quick(0)
+    }
+
+    fn put_buffer_uni(&mut self, _buf: &[char]) -> WriteResponse {
+        WriteResponse::quick(0)
+    }
+
+    fn get_char(&mut self) -> Option<u8> {
+        let ch = *self.bytes().get(self.cursor)?;
+        self.cursor += 1;
+        Some(ch)
+    }
+
+    fn get_buffer(&mut self, maxlen: Option<usize>) -> Vec<u8> {
+        self.get_bytes(maxlen, None)
+    }
+
+    fn get_line(&mut self, maxlen: Option<usize>) -> Vec<u8> {
+        self.get_bytes(maxlen, Some(b'\n'))
+    }
+
+    fn get_char_uni(&mut self) -> Option<char> {
+        // TEXT resources opened as unicode are UTF-8, and BINA resources hold four-byte
+        // big-endian characters
+        if self.unicode && self.is_text {
+            return self.get_utf8_char();
+        }
+
+        let mut result = 0u32;
+        for _ in 0..4 {
+            result = (result << 8) | (self.get_char()? as u32);
+        }
+
+        char::from_u32(result)
+    }
+
+    fn get_buffer_uni(&mut self, maxlen: Option<usize>) -> String {
+        self.get_uni(maxlen, None)
+    }
+
+    fn get_line_uni(&mut self, maxlen: Option<usize>) -> String {
+        self.get_uni(maxlen, Some('\n'))
+    }
+
+    fn get_position(&self) -> u32 {
+        self.cursor as u32
+    }
+
+    fn dropped_line_break(&self) -> bool {
+        self.line_break
+    }
+
+    fn reads_unicode(&self) -> bool {
+        self.unicode
+    }
+
+    fn set_position(&mut self, pos: i32, seekmode: GlkSeekMode) -> Option<()> {
+        let len = self.len as i32;
+        let new_cursor = match seekmode {
+            GlkSeekMode::Start => pos,
+            GlkSeekMode::Current => self.cursor as i32 + pos,
+            GlkSeekMode::End => len + pos,
+        };
+
+        if new_cursor < 0 || new_cursor > len {
+            None
+        } else {
+            self.cursor = new_cursor as usize;
+            Some(())
+        }
+    }
+
+    fn get_data(&self) -> Vec<u8> {
+        self.bytes().to_vec()
+    }
+
+    fn is_window_stream(&self) -> bool {
+        false
+    }
+
+    fn is_memory_stream(&self) -> bool {
+        false
+    }
+}
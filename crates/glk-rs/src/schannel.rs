@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+use crate::prelude::GlkRock;
+
+/// An opaque sound channel ID
+pub type GlkSChannelID = u32;
+
+// Sound channels only keep track of their rocks for now; nothing is played through them
+#[derive(Default)]
+pub(crate) struct SoundChannelManager {
+    channels: BTreeMap<GlkSChannelID, GlkRock>,
+    val: GlkSChannelID,
+}
+
+impl SoundChannelManager {
+    pub(crate) fn create(&mut self, rock: GlkRock) -> GlkSChannelID {
+        self.channels.insert(self.val, rock);
+        self.val += 1;
+        self.val - 1
+    }
+
+    pub(crate) fn destroy(&mut self, chan: GlkSChannelID) {
+        self.channels.remove(&chan);
+    }
+
+    pub(crate) fn get_rock(&self, chan: GlkSChannelID) -> Option<GlkRock> {
+        self.channels.get(&chan).copied()
+    }
+
+    pub(crate) fn get_iter(&self) -> std::vec::IntoIter<GlkSChannelID> {
+        self.channels
+            .keys()
+            .copied()
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
@@ -4,6 +4,7 @@ use std::sync::mpsc::Receiver;
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::entry::GlkResult;
+use crate::style::GlkStyle;
 use crate::{prelude::GlkRock, GlkFileMode, GlkSeekMode};
 
 /// An opaque stream ID
@@ -15,7 +16,20 @@ pub(crate) struct StreamManager {
     val: GlkStreamID,
 }
 
-/// The stats from the stream that is being closed
+/// The contents of a memory stream that is being closed, in the shape the stream was
+/// opened with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamCloseData {
+    /// the stream was not a memory stream
+    None,
+    /// the buffer of a byte memory stream
+    Bytes(Vec<u8>),
+    /// the buffer of a unicode memory stream
+    Unicode(Vec<u32>),
+}
+
+/// The stats from the stream that is being closed. Glk reports these as 32-bit values, so
+/// a count that goes past u32::MAX is reported as u32::MAX instead of wrapping around
 #[derive(Debug, Default, Clone)]
 pub struct GlkStreamResult {
     /// number of characters that were read from this stream
@@ -53,6 +67,8 @@ pub(crate) struct GlkStream {
     _rock: GlkRock,
     read_count: usize,
     write_count: usize,
+    lines_read: usize,
+    style: GlkStyle,
 }
 
 impl GlkStream {
@@ -67,121 +83,189 @@ impl GlkStream {
             _rock,
             read_count: 0,
             write_count: 0,
+            lines_read: 0,
+            style: GlkStyle::Normal,
         }
     }
 
-    pub(crate) fn await_response(&mut self, response: &Receiver<GlkResult>) {
+    // returns how many characters the back end reported writing
+    pub(crate) fn await_response(&mut self, response: &Receiver<GlkResult>) -> usize {
         let Ok(result) = response.recv() else {
-            return;
+            return 0;
         };
 
         let GlkResult::Result(len) = result else {
-            return;
+            return 0;
         };
 
         self.write_count += len;
+        len
     }
 
+    // writing to an input-only stream, or reading from an output-only one, does nothing
     fn check_write(&self) -> bool {
-        if matches!(
-            self.mode,
-            GlkFileMode::Write | GlkFileMode::ReadWrite | GlkFileMode::WriteAppend
-        ) {
-            true
-        } else {
-            panic!("cannot write to a non-writable stream");
-        }
+        self.mode.is_write()
     }
 
     fn check_read(&self) -> bool {
-        if matches!(self.mode, GlkFileMode::Read | GlkFileMode::ReadWrite) {
-            true
-        } else {
-            panic!("cannot read from a non-readable stream");
-        }
+        self.mode.is_read()
     }
 
     pub fn put_char(&mut self, ch: u8) -> WriteResponse {
-        self.check_write();
+        if !self.check_write() {
+            return WriteResponse::quick(0);
+        }
         let response = self.sh.borrow_mut().put_char(ch);
         self.write_count += response.len;
         response
     }
 
     pub fn put_string(&mut self, s: &str) -> WriteResponse {
-        self.check_write();
+        if !self.check_write() {
+            return WriteResponse::quick(0);
+        }
         let response = self.sh.borrow_mut().put_string(s);
         self.write_count += response.len;
         response
     }
 
     pub fn put_buffer(&mut self, buf: &[u8]) -> WriteResponse {
-        self.check_write();
+        if !self.check_write() {
+            return WriteResponse::quick(0);
+        }
         let response = self.sh.borrow_mut().put_buffer(buf);
         self.write_count += response.len;
         response
     }
 
     pub fn put_char_uni(&mut self, ch: char) -> WriteResponse {
-        self.check_write();
+        if !self.check_write() {
+            return WriteResponse::quick(0);
+        }
         let response = self.sh.borrow_mut().put_char_uni(ch);
         self.write_count += response.len;
         response
     }
 
     pub fn put_buffer_uni(&mut self, buf: &[char]) -> WriteResponse {
-        self.check_write();
+        if !self.check_write() {
+            return WriteResponse::quick(0);
+        }
         let response = self.sh.borrow_mut().put_buffer_uni(buf);
         self.write_count += response.len;
         response
     }
 
+    pub fn flush(&mut self) -> WriteResponse {
+        let response = self.sh.borrow_mut().flush();
+        self.write_count += response.len;
+        response
+    }
+
     pub fn get_char(&mut self) -> Option<u8> {
-        self.check_read();
+        if !self.check_read() {
+            return None;
+        }
         let ch = self.sh.borrow_mut().get_char();
         if ch.is_some() {
             self.read_count += 1;
         }
+        self.lines_read += usize::from(ch == Some(b'\n'));
         ch
     }
 
     pub fn get_buffer(&mut self, maxlen: Option<usize>) -> Vec<u8> {
-        self.check_read();
+        if !self.check_read() {
+            return Vec::new();
+        }
         let result = self.sh.borrow_mut().get_buffer(maxlen);
         self.read_count += result.len();
+        self.count_lines(result.iter().map(|&ch| ch as char));
         result
     }
 
     pub fn get_line(&mut self, maxlen: Option<usize>) -> Vec<u8> {
-        self.check_read();
+        if !self.check_read() {
+            return Vec::new();
+        }
         let result = self.sh.borrow_mut().get_line(maxlen);
         self.read_count += result.len();
+        self.count_lines(result.iter().map(|&ch| ch as char));
         result
     }
 
     pub fn get_char_uni(&mut self) -> Option<char> {
-        self.check_read();
+        if !self.check_read() {
+            return None;
+        }
         let ch = self.sh.borrow_mut().get_char_uni();
         if ch.is_some() {
             self.read_count += 4;
         }
+        self.lines_read += usize::from(ch == Some('\n'));
         ch
     }
 
     pub fn get_buffer_uni(&mut self, maxlen: Option<usize>) -> String {
-        self.check_read();
+        if !self.check_read() {
+            return String::new();
+        }
         let result = self.sh.borrow_mut().get_buffer_uni(maxlen);
         self.read_count += result.len() * 4;
+        self.count_lines(result.chars());
         result
     }
 
     pub fn get_line_uni(&mut self, maxlen: Option<usize>) -> String {
-        self.check_read();
+        if !self.check_read() {
+            return String::new();
+        }
         let result = self.sh.borrow_mut().get_line_uni(maxlen);
         self.read_count += result.len() * 4;
+        self.count_lines(result.chars());
         result
     }
 
+    pub fn read_to_string(&mut self) -> Option<String> {
+        if !self.check_read() {
+            return None;
+        }
+
+        if !self.reads_unicode() {
+            let bytes = self.get_buffer(None);
+            return Some(bytes.into_iter().map(|byte| byte as char).collect());
+        }
+
+        let mut result = String::new();
+        while let Some(ch) = self.get_char_uni() {
+            result.push(ch);
+        }
+        Some(result)
+    }
+
+    // some streams read the line break at the end of a line without returning it
+    fn count_lines(&mut self, text: impl Iterator<Item = char>) {
+        self.lines_read += text.filter(|&ch| ch == '\n').count();
+        self.lines_read += usize::from(self.sh.borrow().dropped_line_break());
+    }
+
+    pub fn get_lines_read(&self) -> u32 {
+        u32::try_from(self.lines_read).unwrap_or(u32::MAX)
+    }
+
+    pub fn get_style(&self) -> GlkStyle {
+        self.style
+    }
+
+    pub fn set_style(&mut self, style: GlkStyle) {
+        self.style = style;
+        self.sh.borrow_mut().set_style(style);
+    }
+
+    pub fn set_hyperlink(&mut self, linkval: u32) {
+        self.sh.borrow_mut().set_hyperlink(linkval);
+    }
+
     pub fn is_window_stream(&self) -> bool {
         self.sh.borrow().is_window_stream()
     }
@@ -190,6 +274,14 @@ impl GlkStream {
         self.sh.borrow().is_memory_stream()
     }
 
+    pub fn reads_unicode(&self) -> bool {
+        self.sh.borrow().reads_unicode()
+    }
+
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.sh.borrow_mut().take_error()
+    }
+
     pub fn get_position(&self) -> u32 {
         self.sh.borrow().get_position()
     }
@@ -202,10 +294,24 @@ impl GlkStream {
         self.sh.borrow().get_data()
     }
 
+    pub fn get_data_uni(&self) -> Vec<u32> {
+        self.sh.borrow().get_data_uni()
+    }
+
+    pub fn close_data(&self) -> StreamCloseData {
+        if !self.is_memory_stream() {
+            StreamCloseData::None
+        } else if self.reads_unicode() {
+            StreamCloseData::Unicode(self.get_data_uni())
+        } else {
+            StreamCloseData::Bytes(self.get_data())
+        }
+    }
+
     pub fn get_results(&self) -> GlkStreamResult {
         GlkStreamResult {
-            read_count: self.read_count as u32,
-            write_count: self.write_count as u32,
+            read_count: u32::try_from(self.read_count).unwrap_or(u32::MAX),
+            write_count: u32::try_from(self.write_count).unwrap_or(u32::MAX),
         }
     }
 
@@ -326,13 +432,61 @@ pub(crate) trait GlkStreamHandler {
     fn get_line_uni(&mut self, maxlen: Option<usize>) -> String;
 
     fn get_position(&self) -> u32;
+
+    // true if the last line read ended at a line break which was left out of the line
+    fn dropped_line_break(&self) -> bool {
+        false
+    }
+
+    // the last IO error the stream ran into, if it has not been asked for yet
+    fn take_error(&mut self) -> Option<std::io::Error> {
+        None
+    }
+
+    // true if the stream holds encoded unicode text, to be read with get_char_uni(),
+    // rather than Latin-1 bytes
+    fn reads_unicode(&self) -> bool {
+        false
+    }
     fn set_position(&mut self, pos: i32, seekmode: GlkSeekMode) -> Option<()>;
 
     fn get_data(&self) -> Vec<u8>;
+    // only unicode memory streams hold their data as codepoints
+    fn get_data_uni(&self) -> Vec<u32> {
+        Vec::new()
+    }
     fn get_echo_stream(&self) -> Option<GlkStreamID>;
 
+    // only window streams have anywhere to show styles
+    fn set_style(&mut self, _style: GlkStyle) {}
+    fn set_hyperlink(&mut self, _linkval: u32) {}
+
+    // send on any output being held back, for streams which buffer it
+    fn flush(&mut self) -> WriteResponse {
+        WriteResponse::quick(0)
+    }
+
     fn close(&mut self);
 
     fn is_window_stream(&self) -> bool;
     fn is_memory_stream(&self) -> bool;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mem_stream::MemStream;
+
+    #[test]
+    fn counts_saturate_instead_of_wrapping() {
+        let mem: Rc<RefCell<dyn GlkStreamHandler>> =
+            Rc::new(RefCell::new(MemStream::new(vec![0u8; 4])));
+        let mut stream = GlkStream::new(&mem, GlkFileMode::ReadWrite, 0);
+        stream.write_count = u32::MAX as usize + 5;
+        stream.read_count = 7;
+
+        let results = stream.get_results();
+        assert_eq!(results.write_count, u32::MAX);
+        assert_eq!(results.read_count, 7);
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+/// The styles which text can be displayed in (Glk spec section 5.5). The library only
+/// says what each style means; how it looks is up to the window back end
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlkStyle {
+    /// The style of normal or body text
+    #[default]
+    Normal,
+    /// Text which is emphasized
+    Emphasized,
+    /// Text which has a particular arrangement of characters, usually fixed-width
+    Preformatted,
+    /// Text which introduces a large section
+    Header,
+    /// Text which introduces a smaller section within a large section
+    Subheader,
+    /// Text which warns of a dangerous condition, or one which the player should pay
+    /// attention to
+    Alert,
+    /// Text which notifies of an interesting condition
+    Note,
+    /// Text which forms a quotation or otherwise abstracted text
+    BlockQuote,
+    /// Text which the player has entered
+    Input,
+    /// A style with no predefined meaning, for the game to use as it likes
+    User1,
+    /// Another style with no predefined meaning
+    User2,
+}
+
+/// The appearance of a style which a game can suggest (Glk spec section 5.5.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StyleHint {
+    /// How much to indent lines of text, relative to the normal style
+    Indentation,
+    /// How much to indent the first line of each paragraph, relative to the others
+    ParaIndentation,
+    /// Left, full, centered or right justification
+    Justification,
+    /// How much to increase or decrease the font size, relative to the normal style
+    Size,
+    /// 1 for heavy, 0 for normal, and -1 for light text
+    Weight,
+    /// 1 for oblique text, 0 for upright
+    Oblique,
+    /// 1 for a proportional font, 0 for fixed-width
+    Proportional,
+    /// The foreground color of the text, as 0x00RRGGBB
+    TextColor,
+    /// The background color of the text, as 0x00RRGGBB
+    BackColor,
+    /// 1 to swap the foreground and background colors
+    ReverseColor,
+}
+
+impl StyleHint {
+    const ALL: [StyleHint; 10] = [
+        Self::Indentation,
+        Self::ParaIndentation,
+        Self::Justification,
+        Self::Size,
+        Self::Weight,
+        Self::Oblique,
+        Self::Proportional,
+        Self::TextColor,
+        Self::BackColor,
+        Self::ReverseColor,
+    ];
+}
+
+/// The style hints which have been set for one type of window
+#[derive(Debug, Default, Clone)]
+pub(crate) struct StyleHints {
+    hints: HashMap<(GlkStyle, StyleHint), i32>,
+}
+
+impl StyleHints {
+    pub(crate) fn set(&mut self, style: GlkStyle, hint: StyleHint, value: i32) {
+        self.hints.insert((style, hint), value);
+    }
+
+    pub(crate) fn clear(&mut self, style: GlkStyle, hint: StyleHint) {
+        self.hints.remove(&(style, hint));
+    }
+
+    pub(crate) fn get(&self, style: GlkStyle, hint: StyleHint) -> Option<i32> {
+        self.hints.get(&(style, hint)).copied()
+    }
+
+    /// two styles look different if any of their hints differ
+    pub(crate) fn distinguish(&self, style1: GlkStyle, style2: GlkStyle) -> bool {
+        StyleHint::ALL
+            .iter()
+            .any(|hint| self.get(style1, *hint) != self.get(style2, *hint))
+    }
+}
@@ -1,7 +1,10 @@
 use crate::entry::{GlkMessage, GlkResult};
-use crate::events::{GlkEvent, LineInput};
+use crate::error::GlkError;
+use crate::events::{CharRequest, GlkEvent, LineInput, LineInputSettings};
+use crate::keycode::Keycode;
 use crate::prelude::GlkRock;
 use crate::stream::{GlkStreamHandler, GlkStreamID, WriteResponse};
+use crate::style::{GlkStyle, StyleHint, StyleHints};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::{Rc, Weak};
@@ -21,17 +24,26 @@ pub struct Window<T: GlkWindow + Default> {
     child1: Option<WindowRef<T>>,
     child2: Option<WindowRef<T>>,
     keywin: KeyWindow,
-    #[cfg(test)]
-    pub window: Rc<RefCell<T>>,
-    #[cfg(not(test))]
-    window: Rc<RefCell<T>>,
+    // the root window is only there to hold the tree together, so it has no back end
+    window: Option<Rc<RefCell<T>>>,
+    // the root window keeps the back end of the top-level window it was opened with,
+    // which reports the size of the whole display
+    display: Option<Rc<RefCell<T>>>,
     stream: GlkStreamID,
     echo_stream: Option<GlkStreamID>,
+    stylehints: StyleHints,
+    line_request: bool,
+    line_settings: LineInputSettings,
+    char_request: Option<CharRequest>,
+    mouse_request: bool,
+    hyperlink_request: bool,
+    // text written while output is buffered, which has not been sent to the back end yet
+    output_buffer: Option<String>,
     command: Option<Sender<GlkMessage>>,
 }
 
 /// Type of window to create
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum GlkWindowType {
     /// A window containing a stream of text
     TextBuffer,
@@ -58,17 +70,82 @@ pub trait GlkWindow {
     /// Primary run loop for stdio or window system
     fn run(&mut self);
 
-    /// returns the size of the window in its measurement system
+    /// returns the size of the window in the back end's own units, such as pixels. text
+    /// windows are converted to characters using cell_size()
     fn get_size(&self) -> GlkWindowSize;
 
     /// sets the location of the cursor in the window
     fn move_cursor(&mut self, x: u32, y: u32);
 
-    /// clear a window - the way windows get cleared depends on their GlkWindowType
+    /// clear a window - the way windows get cleared depends on their GlkWindowType. a text
+    /// grid must fill every cell with spaces; its cursor is moved to (0,0) afterwards
     fn clear(&mut self);
 
     /// read a line from a window and transmit it to the event queue - must run separate thread
     fn get_line(&mut self, event: LineInput, initlen: usize, tx: Sender<GlkEvent>);
+
+    /// called before get_line() with how the line should be read: whether to echo it once
+    /// it is entered, and which keys end it as well as Return. the key which ended the line
+    /// goes in the event's terminator
+    fn set_line_settings(&mut self, _settings: &LineInputSettings) {}
+
+    /// read a single keystroke from a window and transmit it to the event queue as a
+    /// GlkEvent::CharInput for the given window - must run separate thread
+    fn get_char(&mut self, _win: GlkWindowID, _tx: Sender<GlkEvent>) {}
+
+    /// stop waiting for the keystroke requested by get_char()
+    fn cancel_char(&mut self) {}
+
+    /// report the next mouse click in a window to the event queue as a GlkEvent::Mouse for
+    /// the given window. text grids measure the position in character cells, and graphics
+    /// windows in pixels
+    fn request_mouse(&mut self, _win: GlkWindowID, _tx: Sender<GlkEvent>) {}
+
+    /// stop waiting for the mouse click requested by request_mouse()
+    fn cancel_mouse(&mut self) {}
+
+    /// report the next hyperlink selected in a window to the event queue as a
+    /// GlkEvent::Hyperlink for the given window
+    fn request_hyperlink(&mut self, _win: GlkWindowID, _tx: Sender<GlkEvent>) {}
+
+    /// stop waiting for the hyperlink requested by request_hyperlink()
+    fn cancel_hyperlink(&mut self) {}
+
+    /// returns how many characters have been typed so far into a pending line input request
+    fn get_line_length(&self) -> usize {
+        0
+    }
+
+    /// place text into a text grid starting at the cursor, advancing the cursor past it.
+    /// returns false if the back end would rather receive grid output as GlkMessage::Write
+    fn put_grid_string(&mut self, _s: &str) -> bool {
+        false
+    }
+
+    /// fill a rectangle of a graphics window with a color, measured in pixels
+    fn fill_rect(&mut self, _color: GlkColor, _x: i32, _y: i32, _w: u32, _h: u32) {}
+
+    /// fill a rectangle of a graphics window with the window's background color
+    fn erase_rect(&mut self, _x: i32, _y: i32, _w: u32, _h: u32) {}
+
+    /// change the background color of a graphics window. nothing is redrawn until the
+    /// window is cleared or erased
+    fn set_background_color(&mut self, _color: GlkColor) {}
+
+    /// the size of one character cell of a text window, in the units the back end reports
+    /// window sizes in. fixed splits are measured in cells for text windows, and directly
+    /// in those units for graphics windows
+    fn cell_size(&self) -> GlkWindowSize {
+        GlkWindowSize {
+            width: 1,
+            height: 1,
+        }
+    }
+
+    /// the optional features this back end supports, as reported by the gestalt system
+    fn capabilities() -> GlkCapabilities {
+        GlkCapabilities::default()
+    }
 }
 
 /// A GLK window reference
@@ -133,6 +210,36 @@ impl<T: GlkWindow + Default> GlkStreamHandler for WindowRef<T> {
         panic!("Library Bug: Should not call this function");
     }
 
+    fn set_style(&mut self, style: GlkStyle) {
+        self.send_message(GlkMessage::SetStyle {
+            winid: self.winref.borrow().this_id,
+            style,
+        });
+    }
+
+    fn set_hyperlink(&mut self, linkval: u32) {
+        self.send_message(GlkMessage::SetHyperlink {
+            winid: self.winref.borrow().this_id,
+            linkval,
+        });
+    }
+
+    fn flush(&mut self) -> WriteResponse {
+        let pending = match self.winref.borrow_mut().output_buffer.as_mut() {
+            Some(pending) if !pending.is_empty() => std::mem::take(pending),
+            _ => return WriteResponse::quick(0),
+        };
+
+        self.send_message(GlkMessage::Write {
+            winid: self.winref.borrow().this_id,
+            message: pending,
+        });
+        WriteResponse {
+            len: 0,
+            wait_needed: true,
+        }
+    }
+
     fn get_position(&self) -> u32 {
         // Glk spec section 5.4, window streams always return 0 for get_position()
         0
@@ -160,8 +267,12 @@ impl<T: GlkWindow + Default> GlkStreamHandler for WindowRef<T> {
     }
 }
 
+/// A color for drawing in graphics windows, as 0xRRGGBB
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GlkColor(pub u32);
+
 /// The size of a window
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct GlkWindowSize {
     /// Width of the window in its measurement system (Glk spec section 1.9)
     pub width: u32,
@@ -170,6 +281,22 @@ pub struct GlkWindowSize {
     pub height: u32,
 }
 
+/// The optional features a back end supports
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GlkCapabilities {
+    /// Can draw in graphics windows and display images sent as GlkMessage::DrawImage
+    pub graphics: bool,
+
+    /// Can play sounds
+    pub sound: bool,
+
+    /// Can report mouse clicks in text grid and graphics windows
+    pub mouse_input: bool,
+
+    /// Can display hyperlinks and report when they are selected
+    pub hyperlinks: bool,
+}
+
 #[derive(Default)]
 pub(crate) struct WindowManager<T: GlkWindow + Default> {
     root: Option<GlkWindowID>,
@@ -184,7 +311,7 @@ impl<T: GlkWindow + Default> WindowManager<T> {
         wintype: WindowType,
         command: Sender<GlkMessage>,
         rock: GlkRock,
-    ) -> Option<GlkWindowID> {
+    ) -> GlkWindowID {
         if self.root.is_none() {
             assert!(self.windows.is_empty());
             let root_win = WindowRef {
@@ -210,18 +337,20 @@ impl<T: GlkWindow + Default> WindowManager<T> {
                 this_id: self.val,
                 parent: Some(Rc::downgrade(&root_win.winref)),
                 command: Some(command),
+                window: Some(Rc::default()),
                 ..Window::default()
             })),
         };
 
         main_win.send_message(GlkMessage::Open(self.val));
+        root_win.winref.borrow_mut().display = main_win.winref.borrow().window.clone();
         root_win.winref.borrow_mut().child1 = Some(main_win.make_clone());
 
         self.windows.insert(self.val, main_win);
 
         self.val += 1;
 
-        Some(self.val - 1)
+        self.val - 1
     }
 
     pub(crate) fn get_root(&self) -> Option<GlkWindowID> {
@@ -258,23 +387,38 @@ impl<T: GlkWindow + Default> WindowManager<T> {
     }
 
     pub(crate) fn get_echo_stream(&self, win: GlkWindowID) -> Option<GlkStreamID> {
-        self.windows.get(&win)?.winref.borrow_mut().echo_stream
+        self.windows.get(&win)?.winref.borrow().echo_stream
+    }
+
+    /// detach a stream from every window that is echoing to it. Each window is borrowed
+    /// on its own, so this is safe no matter how the windows are linked together
+    pub(crate) fn remove_echo_stream(&self, stream: GlkStreamID) {
+        for window in self.windows.values() {
+            let mut window = window.winref.borrow_mut();
+            if window.echo_stream == Some(stream) {
+                window.echo_stream = None;
+            }
+        }
     }
 
     pub(crate) fn split(
         &mut self,
         parent: GlkWindowID,
         method: Option<WindowSplitMethod>,
+        key: WindowSplitKey,
         wintype: WindowType,
         command: Sender<GlkMessage>,
         rock: GlkRock,
     ) -> Option<GlkWindowID> {
         let parentwin = self.windows.get(&parent)?;
+        if parentwin.winref.borrow().wintype == WindowType::Root {
+            return None;
+        }
 
-        let (pairwin, newwin) = parentwin.split(method.clone(), wintype, command, rock);
+        let (pairwin, newwin) = parentwin.split(method.clone(), key, wintype, command, rock);
 
         pairwin.winref.borrow_mut().this_id = self.val;
-        // pairwin.winref.borrow().window.borrow_mut().init(self.val);
+        // pairwin.winref.borrow().backend().borrow_mut().init(self.val);
         self.windows.insert(self.val, pairwin);
         self.val += 1;
 
@@ -284,6 +428,7 @@ impl<T: GlkWindow + Default> WindowManager<T> {
             winid: self.val,
             wintype: newwin.get_type(),
             method,
+            key,
         });
         self.windows.insert(self.val, newwin);
         self.val += 1;
@@ -311,6 +456,24 @@ impl<T: GlkWindow + Default> WindowRef<T> {
     }
 
     fn write_string(&self, s: &str) -> WriteResponse {
+        // text grids place characters at the cursor rather than appending them
+        if self.winref.borrow().wintype == WindowType::TextGrid
+            && self
+                .winref
+                .borrow()
+                .backend()
+                .borrow_mut()
+                .put_grid_string(s)
+        {
+            return WriteResponse::quick(s.chars().count());
+        }
+
+        // buffered text is counted once the back end has written it
+        if let Some(pending) = self.winref.borrow_mut().output_buffer.as_mut() {
+            pending.push_str(s);
+            return WriteResponse::quick(0);
+        }
+
         self.send_message(GlkMessage::Write {
             winid: self.winref.borrow().this_id,
             message: s.to_string(),
@@ -321,18 +484,121 @@ impl<T: GlkWindow + Default> WindowRef<T> {
         }
     }
 
-    pub(crate) fn get_line(&self, input: LineInput, initlen: usize, tx: Sender<GlkEvent>) {
+    pub(crate) fn get_line(
+        &self,
+        input: LineInput,
+        initlen: usize,
+        settings: &LineInputSettings,
+        tx: Sender<GlkEvent>,
+    ) {
+        let window = self.winref.borrow();
+        let mut backend = window.backend().borrow_mut();
+        backend.set_line_settings(settings);
+        backend.get_line(input, initlen, tx);
+    }
+
+    pub(crate) fn set_buffered(&self, buffered: bool) {
+        let mut window = self.winref.borrow_mut();
+        if buffered {
+            window.output_buffer.get_or_insert_with(String::new);
+        } else {
+            window.output_buffer = None;
+        }
+    }
+
+    pub(crate) fn set_line_echo(&self, echo: bool) {
+        self.winref.borrow_mut().line_settings.echo = echo;
+    }
+
+    pub(crate) fn set_line_terminators(&self, terminators: Vec<Keycode>) {
+        self.winref.borrow_mut().line_settings.terminators = terminators;
+    }
+
+    pub(crate) fn get_line_settings(&self) -> LineInputSettings {
+        self.winref.borrow().line_settings.clone()
+    }
+
+    pub(crate) fn get_char(&self, tx: Sender<GlkEvent>) {
+        let win = self.id();
         self.winref
             .borrow()
-            .window
+            .backend()
             .borrow_mut()
-            .get_line(input, initlen, tx);
+            .get_char(win, tx);
     }
 
-    pub(crate) fn remove_echo_stream_if_matches(&mut self, stream: GlkStreamID) {
-        if self.winref.borrow().echo_stream == Some(stream) {
-            self.winref.borrow_mut().echo_stream = None;
-        }
+    pub(crate) fn cancel_char(&self) {
+        self.winref.borrow().backend().borrow_mut().cancel_char();
+    }
+
+    pub(crate) fn set_char_request(&self, request: Option<CharRequest>) {
+        self.winref.borrow_mut().char_request = request;
+    }
+
+    pub(crate) fn get_char_request(&self) -> Option<CharRequest> {
+        self.winref.borrow().char_request
+    }
+
+    pub(crate) fn request_mouse(&self, tx: Sender<GlkEvent>) {
+        let win = self.id();
+        self.winref.borrow_mut().mouse_request = true;
+        self.winref
+            .borrow()
+            .backend()
+            .borrow_mut()
+            .request_mouse(win, tx);
+    }
+
+    pub(crate) fn cancel_mouse(&self) {
+        self.winref.borrow().backend().borrow_mut().cancel_mouse();
+        self.set_mouse_request(false);
+    }
+
+    pub(crate) fn set_mouse_request(&self, pending: bool) {
+        self.winref.borrow_mut().mouse_request = pending;
+    }
+
+    pub(crate) fn has_mouse_request(&self) -> bool {
+        self.winref.borrow().mouse_request
+    }
+
+    pub(crate) fn request_hyperlink(&self, tx: Sender<GlkEvent>) {
+        let win = self.id();
+        self.winref.borrow_mut().hyperlink_request = true;
+        self.winref
+            .borrow()
+            .backend()
+            .borrow_mut()
+            .request_hyperlink(win, tx);
+    }
+
+    pub(crate) fn cancel_hyperlink(&self) {
+        self.winref
+            .borrow()
+            .backend()
+            .borrow_mut()
+            .cancel_hyperlink();
+        self.set_hyperlink_request(false);
+    }
+
+    pub(crate) fn set_hyperlink_request(&self, pending: bool) {
+        self.winref.borrow_mut().hyperlink_request = pending;
+    }
+
+    pub(crate) fn has_hyperlink_request(&self) -> bool {
+        self.winref.borrow().hyperlink_request
+    }
+
+    pub(crate) fn set_line_request(&self, pending: bool) {
+        self.winref.borrow_mut().line_request = pending;
+    }
+
+    pub(crate) fn has_line_request(&self) -> bool {
+        self.winref.borrow().line_request
+    }
+
+    pub(crate) fn get_line_length(&self) -> usize {
+        self.winref.borrow().backend().borrow().get_line_length()
     }
 
     pub(crate) fn id(&self) -> GlkWindowID {
@@ -387,6 +653,7 @@ impl<T: GlkWindow + Default> WindowRef<T> {
     pub(crate) fn split(
         &self,
         method: Option<WindowSplitMethod>,
+        key: WindowSplitKey,
         wintype: WindowType,
         command: Sender<GlkMessage>,
         rock: GlkRock,
@@ -398,6 +665,7 @@ impl<T: GlkWindow + Default> WindowRef<T> {
                 wintype,
                 rock,
                 command: Some(command.clone()),
+                window: Some(Rc::default()),
                 ..Window::default()
             })),
         };
@@ -408,8 +676,12 @@ impl<T: GlkWindow + Default> WindowRef<T> {
                 method,
                 child1: Some(self.make_clone()),
                 child2: Some(new_win.make_clone()),
-                keywin: KeyWindow::Child2,
+                keywin: match key {
+                    WindowSplitKey::NewWindow => KeyWindow::Child2,
+                    WindowSplitKey::ExistingWindow => KeyWindow::Child1,
+                },
                 command: Some(command),
+                window: Some(Rc::default()),
                 ..Window::default()
             })),
         };
@@ -503,14 +775,8 @@ impl<T: GlkWindow + Default> WindowRef<T> {
 
     /// returns the type of this window
     pub(crate) fn get_type(&self) -> GlkWindowType {
-        match self.winref.borrow().wintype {
-            WindowType::Blank => GlkWindowType::Blank,
-            WindowType::TextBuffer => GlkWindowType::TextBuffer,
-            WindowType::TextGrid => GlkWindowType::TextGrid,
-            WindowType::Graphics => GlkWindowType::Graphics,
-            WindowType::Pair => GlkWindowType::Pair,
-            _ => panic!("internal window type only"),
-        }
+        GlkWindowType::try_from(self.winref.borrow().wintype.clone())
+            .expect("internal window type only")
     }
 
     /// returns the rock value for this window
@@ -539,13 +805,110 @@ impl<T: GlkWindow + Default> WindowRef<T> {
         }
     }
 
+    /// the size of the window in its own measurement system: rows and columns of
+    /// characters for text windows, and the back end's units for everything else
     pub(crate) fn get_size(&self) -> GlkWindowSize {
-        self.winref.borrow().window.borrow().get_size()
+        let size = self.get_raw_size();
+        let unit = self.unit_size();
+        GlkWindowSize {
+            width: size.width / unit.width.max(1),
+            height: size.height / unit.height.max(1),
+        }
+    }
+
+    /// the size of the window in the units the back end measures in. the top-level window
+    /// fills the display, whose size comes from the back end of the window the display was
+    /// opened with, and every split window takes its share of its pair window's size. the
+    /// sizes are worked out each time they are asked for, so they follow the display when
+    /// the back end resizes it and reports that with an Arrange event
+    pub(crate) fn get_raw_size(&self) -> GlkWindowSize {
+        let Some(parent) = self.get_parent() else {
+            return self.winref.borrow().backend().borrow().get_size();
+        };
+        if parent.winref.borrow().wintype == WindowType::Pair {
+            return parent.get_child_size(self);
+        }
+
+        let root = parent.winref.borrow();
+        match root.display.as_ref() {
+            Some(display) => display.borrow().get_size(),
+            None => self.winref.borrow().backend().borrow().get_size(),
+        }
     }
 
-    pub(crate) fn set_arrangement(&self, method: WindowSplitMethod, keywin: Option<&WindowRef<T>>) {
+    fn get_child_size(&self, child: &WindowRef<T>) -> GlkWindowSize {
+        let size = self.get_raw_size();
+        let pair = self.winref.borrow();
+        let Some(method) = pair.method.as_ref() else {
+            return size;
+        };
+
+        let across = matches!(
+            method.position,
+            WindowSplitPosition::Left | WindowSplitPosition::Right
+        );
+        let key = match pair.keywin {
+            KeyWindow::Child1 => pair.child1.as_ref(),
+            KeyWindow::Child2 => pair.child2.as_ref(),
+            KeyWindow::None => None,
+        };
+
+        let total = if across { size.width } else { size.height };
+        let key_share = match method.amount {
+            WindowSplitAmount::Fixed(amount) => {
+                let unit = key.map_or(1, |key| {
+                    let cell = key.unit_size();
+                    if across {
+                        cell.width
+                    } else {
+                        cell.height
+                    }
+                });
+                (amount.max(0) as u32).saturating_mul(unit).min(total)
+            }
+            WindowSplitAmount::Proportional(percent) => total * percent.clamp(0, 100) as u32 / 100,
+        };
+        let share = if key.is_some_and(|key| Rc::ptr_eq(&key.winref, &child.winref)) {
+            key_share
+        } else {
+            total - key_share
+        };
+
+        if across {
+            GlkWindowSize {
+                width: share,
+                height: size.height,
+            }
+        } else {
+            GlkWindowSize {
+                width: size.width,
+                height: share,
+            }
+        }
+    }
+
+    // the size of one unit of this window's measurement system, which fixed splits keyed
+    // to it are also measured in: a character cell for text windows, and whatever the
+    // back end measures in for everything else
+    fn unit_size(&self) -> GlkWindowSize {
+        let window = self.winref.borrow();
+        match window.wintype {
+            WindowType::TextBuffer | WindowType::TextGrid => window.backend().borrow().cell_size(),
+            _ => GlkWindowSize {
+                width: 1,
+                height: 1,
+            },
+        }
+    }
+
+    /// returns false if this is not a pair window, in which case nothing is changed
+    pub(crate) fn set_arrangement(
+        &self,
+        method: WindowSplitMethod,
+        keywin: Option<&WindowRef<T>>,
+    ) -> bool {
         if self.winref.borrow().wintype != WindowType::Pair {
-            return;
+            return false;
         }
 
         self.winref.borrow_mut().method = Some(method);
@@ -559,6 +922,7 @@ impl<T: GlkWindow + Default> WindowRef<T> {
                 self.winref.borrow_mut().keywin = KeyWindow::Child2;
             }
         }
+        true
     }
 
     /// returns the constraints of the window
@@ -583,12 +947,56 @@ impl<T: GlkWindow + Default> WindowRef<T> {
 
     pub(crate) fn move_cursor(&self, x: u32, y: u32) {
         if self.winref.borrow().wintype == WindowType::TextGrid {
-            self.winref.borrow().window.borrow_mut().move_cursor(x, y);
+            self.winref
+                .borrow()
+                .backend()
+                .borrow_mut()
+                .move_cursor(x, y);
+        }
+    }
+
+    // only graphics windows can be drawn in
+    fn graphics_backend(&self) -> Option<Rc<RefCell<T>>> {
+        let window = self.winref.borrow();
+        if window.wintype != WindowType::Graphics {
+            return None;
+        }
+        window.window.clone()
+    }
+
+    pub(crate) fn fill_rect(&self, color: GlkColor, x: i32, y: i32, w: u32, h: u32) {
+        if let Some(backend) = self.graphics_backend() {
+            backend.borrow_mut().fill_rect(color, x, y, w, h);
+        }
+    }
+
+    pub(crate) fn erase_rect(&self, x: i32, y: i32, w: u32, h: u32) {
+        if let Some(backend) = self.graphics_backend() {
+            backend.borrow_mut().erase_rect(x, y, w, h);
+        }
+    }
+
+    pub(crate) fn set_background_color(&self, color: GlkColor) {
+        if let Some(backend) = self.graphics_backend() {
+            backend.borrow_mut().set_background_color(color);
         }
     }
 
     pub(crate) fn clear(&self) {
-        self.winref.borrow().window.borrow_mut().clear();
+        self.winref.borrow().backend().borrow_mut().clear();
+        self.move_cursor(0, 0);
+    }
+
+    /// style hints are copied into a window when it is opened, so later changes to them
+    /// don't affect it
+    pub(crate) fn set_stylehints(&self, stylehints: StyleHints) {
+        self.winref.borrow_mut().stylehints = stylehints;
+    }
+
+    /// returns the hint that was suggested for a style when this window was opened, or
+    /// None if the back end should use its own default
+    pub fn get_stylehint(&self, style: GlkStyle, hint: StyleHint) -> Option<i32> {
+        self.winref.borrow().stylehints.get(style, hint)
     }
 
     pub(crate) fn get_stream(&self) -> GlkStreamID {
@@ -617,6 +1025,26 @@ pub struct WindowSplitMethod {
     pub border: bool,
 }
 
+impl WindowSplitMethod {
+    pub(crate) fn is_valid(&self) -> bool {
+        match self.amount {
+            WindowSplitAmount::Fixed(_) => true,
+            WindowSplitAmount::Proportional(percent) => (0..=100).contains(&percent),
+        }
+    }
+}
+
+/// Which window of a split the split amount is measured against (Glk spec section 3.2)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WindowSplitKey {
+    /// The amount applies to the newly created window
+    #[default]
+    NewWindow,
+
+    /// The amount applies to the window that was split
+    ExistingWindow,
+}
+
 /// Describes where the new window should be placed in relation to the existing window
 #[derive(Clone, Debug, PartialEq)]
 pub enum WindowSplitPosition {
@@ -636,10 +1064,11 @@ pub enum WindowSplitPosition {
 /// How the new window should be sized in relation to the existing window
 #[derive(Clone, Debug, PartialEq)]
 pub enum WindowSplitAmount {
-    /// New window should have a fixed number of lines/columns
+    /// The key window should have a fixed size: a number of rows or columns of characters
+    /// for text windows, or of pixels for graphics windows
     Fixed(i32),
 
-    /// New window should consume a percentage of the existing window
+    /// New window should consume a percentage (0 to 100) of the existing window
     Proportional(i32),
 }
 
@@ -666,49 +1095,178 @@ pub(crate) enum WindowType {
     Root,
 }
 
-impl<T: GlkWindow + Default> Window<T> {}
+impl From<GlkWindowType> for WindowType {
+    fn from(wintype: GlkWindowType) -> Self {
+        match wintype {
+            GlkWindowType::TextBuffer => WindowType::TextBuffer,
+            GlkWindowType::TextGrid => WindowType::TextGrid,
+            GlkWindowType::Graphics => WindowType::Graphics,
+            GlkWindowType::Blank => WindowType::Blank,
+            GlkWindowType::Pair => WindowType::Pair,
+        }
+    }
+}
+
+/// the root window is internal to the library and has no public type
+impl TryFrom<WindowType> for GlkWindowType {
+    type Error = GlkError;
+
+    fn try_from(wintype: WindowType) -> Result<Self, Self::Error> {
+        match wintype {
+            WindowType::TextBuffer => Ok(GlkWindowType::TextBuffer),
+            WindowType::TextGrid => Ok(GlkWindowType::TextGrid),
+            WindowType::Graphics => Ok(GlkWindowType::Graphics),
+            WindowType::Blank => Ok(GlkWindowType::Blank),
+            WindowType::Pair => Ok(GlkWindowType::Pair),
+            WindowType::Root => Err(GlkError::InvalidWindowType),
+        }
+    }
+}
+
+impl<T: GlkWindow + Default> Window<T> {
+    pub(crate) fn backend(&self) -> &RefCell<T> {
+        self.window
+            .as_deref()
+            .expect("the root window has no back end")
+    }
+}
 
 #[cfg(test)]
 pub mod testwin {
     use super::*;
+    use crate::Glk;
+    use std::cell::Cell;
+    use std::sync::mpsc;
+
+    thread_local! {
+        /// how many back end windows have been created on this thread
+        pub static BACKENDS_CREATED: Cell<usize> = const { Cell::new(0) };
+
+        /// the features the test back ends on this thread report
+        pub static CAPABILITIES: Cell<GlkCapabilities> = const {
+            Cell::new(GlkCapabilities {
+                graphics: true,
+                sound: false,
+                mouse_input: true,
+                hyperlinks: true,
+            })
+        };
+    }
 
     #[derive(Debug)]
     pub struct GlkTestWindow {
         pub winid: GlkWindowID,
         pub width: u32,
         pub height: u32,
+        /// the size of a character cell, in the units of width and height
+        pub cell: GlkWindowSize,
         pub cursor_x: u32,
         pub cursor_y: u32,
         pub textdata: String, // output buffer
+        /// text grid cells, indexed by [y][x]
+        pub grid: Vec<Vec<char>>,
+        /// output received by the back end, split at line breaks
+        pub lines: Vec<String>,
+        /// the style of output received by the back end
+        pub style: GlkStyle,
+        /// each write, tagged with the style it was written in
+        pub styled: Vec<(GlkStyle, String)>,
+        /// the background color of a graphics window
+        pub background: GlkColor,
+        /// every rectangle drawn in a graphics window, as (color, x, y, width, height)
+        pub rects: Vec<(GlkColor, i32, i32, u32, u32)>,
+        /// where to send the next mouse click, while one is requested
+        pub mouse: Option<(GlkWindowID, Sender<GlkEvent>)>,
+        /// the settings of the last line input request
+        pub line_settings: LineInputSettings,
+        /// where to send the line being entered, while line input is requested
+        pub line_tx: Option<Sender<GlkEvent>>,
+        /// the current hyperlink
+        pub hyperlink: u32,
+        /// each write, tagged with the hyperlink it was written under
+        pub linked: Vec<(u32, String)>,
+        /// where to send the next selected link, while one is requested
+        pub link_request: Option<(GlkWindowID, Sender<GlkEvent>)>,
         pub input_buffer: RefCell<Vec<char>>,
         pub input_cursor: RefCell<usize>,
         pub output_bytes: usize,
         pub input_bytes: usize,
+        /// the channel messages from glk arrive on
+        pub request: Option<Receiver<GlkMessage>>,
+        /// the channel responses to glk are sent on
+        pub result: Option<Sender<GlkResult>>,
     }
 
     impl Default for GlkTestWindow {
         fn default() -> Self {
+            BACKENDS_CREATED.with(|count| count.set(count.get() + 1));
             Self {
                 winid: 0,
                 width: 12,
                 height: 32,
+                cell: GlkWindowSize {
+                    width: 1,
+                    height: 1,
+                },
                 cursor_x: 0,
                 cursor_y: 0,
                 textdata: String::new(),
+                lines: Vec::new(),
+                style: GlkStyle::Normal,
+                styled: Vec::new(),
+                background: GlkColor::default(),
+                rects: Vec::new(),
+                mouse: None,
+                line_settings: LineInputSettings::default(),
+                line_tx: None,
+                hyperlink: 0,
+                linked: Vec::new(),
+                link_request: None,
+                grid: Vec::new(),
                 input_buffer: RefCell::new(Vec::new()),
                 input_cursor: RefCell::new(0),
                 output_bytes: 0,
                 input_bytes: 0,
+                request: None,
+                result: None,
             }
         }
     }
 
     impl super::GlkWindow for GlkTestWindow {
-        fn new(_request: Receiver<GlkMessage>, _result: Sender<GlkResult>) -> Self {
-            Self::default()
+        fn new(request: Receiver<GlkMessage>, result: Sender<GlkResult>) -> Self {
+            Self {
+                request: Some(request),
+                result: Some(result),
+                ..Self::default()
+            }
         }
 
-        fn run(&mut self) {}
+        // report the length of every write back to glk, like a real back end would
+        fn run(&mut self) {
+            let (Some(request), Some(result)) = (self.request.take(), self.result.take()) else {
+                return;
+            };
+
+            while let Ok(message) = request.recv() {
+                if let GlkMessage::SetStyle { style, .. } = message {
+                    self.style = style;
+                } else if let GlkMessage::SetHyperlink { linkval, .. } = message {
+                    self.hyperlink = linkval;
+                } else if let GlkMessage::Write { message, .. } = message {
+                    self.output_bytes += message.len();
+                    self.styled.push((self.style, message.clone()));
+                    self.linked.push((self.hyperlink, message.clone()));
+                    for (i, line) in message.split('\n').enumerate() {
+                        if i > 0 || self.lines.is_empty() {
+                            self.lines.push(String::new());
+                        }
+                        self.lines.last_mut().unwrap().push_str(line);
+                    }
+                    let _ = result.send(GlkResult::Result(message.chars().count()));
+                }
+            }
+        }
 
         fn get_size(&self) -> GlkWindowSize {
             GlkWindowSize {
@@ -722,17 +1280,154 @@ pub mod testwin {
             self.cursor_y = y;
         }
 
+        fn fill_rect(&mut self, color: GlkColor, x: i32, y: i32, w: u32, h: u32) {
+            self.rects.push((color, x, y, w, h));
+        }
+
+        fn erase_rect(&mut self, x: i32, y: i32, w: u32, h: u32) {
+            self.rects.push((self.background, x, y, w, h));
+        }
+
+        fn set_background_color(&mut self, color: GlkColor) {
+            self.background = color;
+        }
+
         fn clear(&mut self) {
             self.cursor_x = 0;
             self.cursor_y = 0;
+            self.grid = vec![vec![' '; self.width as usize]; self.height as usize];
+        }
+
+        fn get_line(&mut self, _event: LineInput, _initlen: usize, tx: Sender<GlkEvent>) {
+            self.line_tx = Some(tx);
+        }
+
+        fn set_line_settings(&mut self, settings: &LineInputSettings) {
+            self.line_settings = settings.clone();
+        }
+
+        // deliver the next character of the input buffer as the keystroke
+        fn get_char(&mut self, win: GlkWindowID, tx: Sender<GlkEvent>) {
+            let mut cursor = self.input_cursor.borrow_mut();
+            let Some(&ch) = self.input_buffer.borrow().get(*cursor) else {
+                return;
+            };
+            *cursor += 1;
+
+            let key = if ch.is_control() {
+                ch.into()
+            } else {
+                Keycode::Basic(ch)
+            };
+            let _ = tx.send(GlkEvent::CharInput { win, key });
+        }
+
+        fn request_mouse(&mut self, win: GlkWindowID, tx: Sender<GlkEvent>) {
+            self.mouse = Some((win, tx));
+        }
+
+        fn cancel_mouse(&mut self) {
+            self.mouse = None;
+        }
+
+        fn request_hyperlink(&mut self, win: GlkWindowID, tx: Sender<GlkEvent>) {
+            self.link_request = Some((win, tx));
+        }
+
+        fn cancel_hyperlink(&mut self) {
+            self.link_request = None;
+        }
+
+        fn get_line_length(&self) -> usize {
+            *self.input_cursor.borrow()
+        }
+
+        fn cell_size(&self) -> GlkWindowSize {
+            self.cell
+        }
+
+        fn capabilities() -> GlkCapabilities {
+            CAPABILITIES.with(|caps| caps.get())
         }
 
-        fn get_line(&mut self, _event: LineInput, _initlen: usize, _tx: Sender<GlkEvent>) {
-            // no-op
+        fn put_grid_string(&mut self, s: &str) -> bool {
+            self.grid
+                .resize(self.height as usize, vec![' '; self.width as usize]);
+            for ch in s.chars() {
+                if self.cursor_x >= self.width {
+                    self.cursor_x = 0;
+                    self.cursor_y += 1;
+                }
+                if self.cursor_y >= self.height {
+                    // output past the bottom of a grid is discarded
+                    break;
+                }
+                if ch == '\n' {
+                    self.cursor_x = 0;
+                    self.cursor_y += 1;
+                    continue;
+                }
+                self.grid[self.cursor_y as usize][self.cursor_x as usize] = ch;
+                self.cursor_x += 1;
+            }
+            true
         }
     }
 
     impl GlkTestWindow {
+        /// run glk like Glk::start() does, and then hand back the back end so that tests
+        /// can look at what it received
+        pub fn run_backend<F: FnOnce(&mut Glk<Self>) + Send + 'static>(func: F) -> Self {
+            let (command, request) = mpsc::channel();
+            let (result, response) = mpsc::channel();
+
+            let joiner = std::thread::spawn(move || {
+                let mut glk = Glk::<Self>::new(command, response);
+                func(&mut glk);
+            });
+
+            let mut backend = <Self as super::GlkWindow>::new(request, result);
+            backend.run();
+            joiner.join().unwrap();
+            backend
+        }
+
+        /// finish the requested line by pressing a key. returns false if the key doesn't
+        /// end the line
+        pub fn end_line(&mut self, win: GlkWindowID, text: &str, key: Keycode) -> bool {
+            if key != Keycode::Return && !self.line_settings.terminators.contains(&key) {
+                return false;
+            }
+            let Some(tx) = self.line_tx.take() else {
+                return false;
+            };
+            let buf: Vec<u8> = text
+                .chars()
+                .map(|ch| u8::try_from(ch).unwrap_or(b'?'))
+                .collect();
+            let _ = tx.send(GlkEvent::LineInput {
+                win,
+                len: buf.len(),
+                buf: LineInput::Latin1(buf),
+                terminator: (key != Keycode::Return).then_some(key),
+            });
+            true
+        }
+
+        /// click in the window, if a mouse event has been requested
+        pub fn click(&mut self, x: u32, y: u32) {
+            if let Some((win, tx)) = self.mouse.take() {
+                let _ = tx.send(GlkEvent::Mouse { win, x, y });
+            }
+        }
+
+        /// select a link in the window, if a hyperlink event has been requested
+        pub fn follow_link(&mut self, linkval: u32) {
+            if let Some((win, tx)) = self.link_request.take() {
+                let _ = tx.send(GlkEvent::Hyperlink { win, linkval });
+            }
+        }
+
         pub fn set_input_buffer(&mut self, s: &str) {
             self.input_buffer = RefCell::new(Vec::from_iter(s.chars()));
             self.input_cursor = RefCell::new(0);
@@ -950,12 +1645,12 @@ mod test {
         // text buffers do not use move_cursor
         let wina_ref = winsys.get_ref(window_a).unwrap();
         wina_ref.move_cursor(4, 4);
-        assert_eq!(wina_ref.winref.borrow().window.borrow().cursor_x, 0);
+        assert_eq!(wina_ref.winref.borrow().backend().borrow().cursor_x, 0);
 
         // text grid windows DO move the cursor
         let winb_ref = winsys.get_ref(window_b).unwrap();
         winb_ref.move_cursor(4, 4);
-        assert_eq!(winb_ref.winref.borrow().window.borrow().cursor_x, 4);
+        assert_eq!(winb_ref.winref.borrow().backend().borrow().cursor_x, 4);
     }
 
     #[test]
@@ -964,9 +1659,9 @@ mod test {
         let window_a = winsys.open_window(WindowType::TextGrid, 32).unwrap();
         let wina_ref = winsys.get_ref(window_a).unwrap();
         wina_ref.move_cursor(5, 5);
-        assert_eq!(wina_ref.winref.borrow().window.borrow().cursor_x, 5);
+        assert_eq!(wina_ref.winref.borrow().backend().borrow().cursor_x, 5);
         wina_ref.clear();
-        assert_eq!(wina_ref.winref.borrow().window.borrow().cursor_x, 0);
+        assert_eq!(wina_ref.winref.borrow().backend().borrow().cursor_x, 0);
     }
 
     #[test]
@@ -977,14 +1672,14 @@ mod test {
         wina_ref
             .winref
             .borrow()
-            .window
+            .backend()
             .borrow_mut()
             .set_input_buffer("test buffer");
         assert_eq!(
             wina_ref
                 .winref
                 .borrow()
-                .window
+                .backend()
                 .borrow()
                 .input_buffer
                 .borrow()